@@ -0,0 +1,214 @@
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct Interval {
+    pub app_name: String,
+    pub title: String,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    pub active: bool,
+    #[serde(default)]
+    pub project: Option<String>,
+    #[serde(default)]
+    pub category: Option<String>,
+}
+
+pub struct Tick<'a> {
+    pub app_name: &'a str,
+    pub title: &'a str,
+    pub project: Option<&'a str>,
+    pub category: Option<&'a str>,
+    pub active: bool,
+}
+
+impl Interval {
+    pub fn duration(&self) -> Duration {
+        (self.end - self.start).to_std().unwrap_or_default()
+    }
+}
+
+// Anything longer than this between two ticks (e.g. the machine was suspended)
+// starts a new interval instead of stretching the previous one over the gap
+const MAX_TICK_GAP: Duration = Duration::from_secs(2);
+
+/// Records a tick of the given window in the log, extending the last interval when
+/// nothing changed since the previous tick. Returns the time added to the log.
+pub fn record_tick(
+    log: &mut Vec<Interval>,
+    tick: &Tick,
+    now: DateTime<Utc>,
+    check_timer: Duration,
+) -> Duration {
+    if let Some(last) = log.last_mut() {
+        let gap = (now - last.end).to_std().unwrap_or_default();
+        if last.app_name == tick.app_name
+            && last.title == tick.title
+            && last.project.as_deref() == tick.project
+            && last.category.as_deref() == tick.category
+            && last.active == tick.active
+            && now >= last.end
+            && gap <= MAX_TICK_GAP
+        {
+            last.end = now;
+            return gap;
+        }
+    }
+    let start = now - chrono::Duration::from_std(check_timer).unwrap_or_default();
+    log.push(Interval {
+        app_name: tick.app_name.to_string(),
+        title: tick.title.to_string(),
+        start,
+        end: now,
+        active: tick.active,
+        project: tick.project.map(str::to_string),
+        category: tick.category.map(str::to_string),
+    });
+    check_timer
+}
+
+pub fn calculate_totals(log: &[Interval]) -> DashMap<String, Duration> {
+    let totals: DashMap<String, Duration> = DashMap::new();
+    for interval in log.iter().filter(|i| i.active) {
+        *totals.entry(interval.app_name.clone()).or_default() += interval.duration();
+    }
+    totals
+}
+
+/// Totals of a day restored from the log, keeping the saved time missing from it, e.g.
+/// imported or recorded before the log existed
+pub fn merge_totals(
+    saved: DashMap<String, Duration>,
+    log: &[Interval],
+) -> DashMap<String, Duration> {
+    for (app_name, logged) in calculate_totals(log) {
+        let mut total = saved.entry(app_name).or_default();
+        *total = (*total).max(logged);
+    }
+    saved
+}
+
+pub fn calculate_title_totals(log: &[Interval]) -> DashMap<String, DashMap<String, Duration>> {
+    let totals: DashMap<String, DashMap<String, Duration>> = DashMap::new();
+    for interval in log.iter().filter(|i| i.active) {
+        *totals
+            .entry(interval.app_name.clone())
+            .or_default()
+            .entry(interval.title.clone())
+            .or_default() += interval.duration();
+    }
+    totals
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tick<'a>(app_name: &'a str, title: &'a str, active: bool) -> Tick<'a> {
+        Tick {
+            app_name,
+            title,
+            project: None,
+            category: None,
+            active,
+        }
+    }
+
+    fn interval(title: &str, seconds: i64, active: bool) -> Interval {
+        let start = Utc::now();
+        Interval {
+            app_name: "time_back".to_string(),
+            title: title.to_string(),
+            start,
+            end: start + chrono::Duration::seconds(seconds),
+            active,
+            project: None,
+            category: None,
+        }
+    }
+
+    #[test]
+    fn should_extend_interval_when_window_does_not_change() {
+        let mut log = vec![];
+        let check_timer = Duration::from_millis(50);
+        let now = Utc::now();
+        let window = tick("time_back", "Time back!", true);
+        record_tick(&mut log, &window, now, check_timer);
+        let added = record_tick(
+            &mut log,
+            &window,
+            now + chrono::Duration::milliseconds(60),
+            check_timer,
+        );
+        assert_eq!(1, log.len());
+        assert_eq!(Duration::from_millis(60), added);
+        assert_eq!(Duration::from_millis(110), log[0].duration());
+    }
+
+    #[test]
+    fn should_start_new_interval_on_change_or_gap() {
+        let mut log = vec![];
+        let check_timer = Duration::from_millis(50);
+        let now = Utc::now();
+        record_tick(
+            &mut log,
+            &tick("time_back", "Time back!", true),
+            now,
+            check_timer,
+        );
+        record_tick(
+            &mut log,
+            &tick("time_back", "Time back!", false),
+            now,
+            check_timer,
+        );
+        record_tick(
+            &mut log,
+            &tick("editor", "main.rs", false),
+            now,
+            check_timer,
+        );
+        record_tick(
+            &mut log,
+            &tick("editor", "main.rs", false),
+            now + chrono::Duration::seconds(60),
+            check_timer,
+        );
+        let mut categorized = tick("editor", "main.rs", false);
+        categorized.category = Some("work");
+        record_tick(
+            &mut log,
+            &categorized,
+            now + chrono::Duration::milliseconds(60_050),
+            check_timer,
+        );
+        assert_eq!(5, log.len());
+        assert_eq!(Some("work"), log[4].category.as_deref());
+    }
+
+    #[test]
+    fn should_calculate_totals_only_from_active_intervals() {
+        let log = vec![
+            interval("Time back!", 10, true),
+            interval("Settings", 5, true),
+            interval("Time back!", 100, false),
+        ];
+        let totals = calculate_totals(&log);
+        assert_eq!(1, totals.len());
+        assert_eq!(Duration::from_secs(15), *totals.get("time_back").unwrap());
+    }
+
+    #[test]
+    fn should_keep_saved_totals_missing_from_the_log() {
+        let saved = DashMap::new();
+        saved.insert("time_back".to_string(), Duration::from_secs(5));
+        saved.insert("imported".to_string(), Duration::from_secs(60));
+        let totals = merge_totals(saved, &[interval("Time back!", 10, true)]);
+        assert_eq!(2, totals.len());
+        assert_eq!(Duration::from_secs(10), *totals.get("time_back").unwrap());
+        assert_eq!(Duration::from_secs(60), *totals.get("imported").unwrap());
+    }
+}
@@ -0,0 +1,322 @@
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex},
+};
+
+use chrono::NaiveDate;
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use tiny_http::{Header, Method, Response, Server};
+
+use crate::{
+    history::{History, Today},
+    report::{self, report, Format, GroupBy, Period},
+    storage::Storage,
+    Config, LiveData, OpenStorage,
+};
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct ApiConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_port")]
+    pub port: u16,
+    /// Sent by the clients as `Authorization: Bearer <token>`
+    #[serde(default)]
+    pub token: String,
+}
+
+impl Default for ApiConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: default_port(),
+            token: String::new(),
+        }
+    }
+}
+
+fn default_port() -> u16 {
+    7117
+}
+
+/// 128 bits from the random generator of the system, as hexadecimal
+pub fn generate_token() -> Result<String, String> {
+    let mut bytes = [0u8; 16];
+    getrandom::fill(&mut bytes).map_err(|e| e.to_string())?;
+    Ok(bytes.iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+/// Serves the live and historical data on localhost only, every request needs the token
+pub fn spawn_api_server(
+    config: &ApiConfig,
+    live: LiveData,
+    history: Arc<Mutex<History>>,
+    app_config: Arc<Mutex<Config>>,
+) -> Result<(), String> {
+    if config.token.is_empty() {
+        return Err("the API needs a token".to_string());
+    }
+    let server = Server::http(("127.0.0.1", config.port)).map_err(|e| e.to_string())?;
+    let token = config.token.clone();
+    std::thread::spawn(move || {
+        let json = Header::from_bytes("Content-Type", "application/json").unwrap();
+        let mut storage = OpenStorage::new(false);
+        for request in server.incoming_requests() {
+            let app_config = app_config.lock().unwrap().clone();
+            let authorization = request
+                .headers()
+                .iter()
+                .find(|h| h.field.equiv("Authorization"))
+                .map(|h| h.value.as_str().to_string());
+            let (status, body) = handle(
+                request.method(),
+                request.url(),
+                authorization.as_deref(),
+                &token,
+                &live,
+                &history,
+                storage.get(&app_config),
+            );
+            let response = Response::from_string(body)
+                .with_status_code(status)
+                .with_header(json.clone());
+            if let Err(e) = request.respond(response) {
+                eprintln!("Unable to answer an API request: {}", e);
+            }
+        }
+    });
+    Ok(())
+}
+
+fn handle(
+    method: &Method,
+    url: &str,
+    authorization: Option<&str>,
+    token: &str,
+    live: &LiveData,
+    history: &Mutex<History>,
+    storage: Option<&dyn Storage>,
+) -> (u16, String) {
+    let authorized = authorization
+        .and_then(|a| a.strip_prefix("Bearer "))
+        .is_some_and(|t| same_token(t.trim(), token));
+    if !authorized {
+        return error(401, "missing or wrong token");
+    }
+    if *method != Method::Get {
+        return error(405, "only GET is supported");
+    }
+    let (path, query) = url.split_once('?').unwrap_or((url, ""));
+    let query = query
+        .split('&')
+        .filter_map(|p| p.split_once('='))
+        .collect::<BTreeMap<_, _>>();
+    let body = match path.trim_end_matches('/') {
+        "/api/window_time" => serde_json::to_value(window_time(live)),
+        "/api/input_stats" => serde_json::to_value(input_stats(live)),
+        "/api/current" => serde_json::to_value(current(live)),
+        "/api/today" => Ok(serde_json::json!({
+            "day": *live.day.lock().unwrap(),
+            "current": current(live),
+            "window_time": window_time(live),
+            "input_stats": input_stats(live),
+        })),
+        "/api/history" => return history_report(&query, live, history, storage),
+        _ => return error(404, "unknown endpoint"),
+    };
+    match body {
+        Ok(body) => (200, body.to_string()),
+        Err(e) => error(500, &e.to_string()),
+    }
+}
+
+fn window_time(live: &LiveData) -> BTreeMap<String, u64> {
+    live.window_time
+        .iter()
+        .map(|v| (v.key().clone(), v.value().as_secs()))
+        .collect()
+}
+
+fn input_stats(live: &LiveData) -> BTreeMap<String, u32> {
+    live.input_stats
+        .iter()
+        .map(|v| (v.key().clone(), *v.value()))
+        .collect()
+}
+
+// The last interval is the window in the foreground, missing when nothing was tracked
+fn current(live: &LiveData) -> Option<serde_json::Value> {
+    live.activity.lock().unwrap().last().map(|interval| {
+        serde_json::json!({
+            "app_name": interval.app_name,
+            "title": interval.title,
+            "active": interval.active,
+            "since": interval.start,
+        })
+    })
+}
+
+fn history_report(
+    query: &BTreeMap<&str, &str>,
+    live: &LiveData,
+    history: &Mutex<History>,
+    storage: Option<&dyn Storage>,
+) -> (u16, String) {
+    let today = *live.day.lock().unwrap();
+    let date = |name: &str| {
+        query
+            .get(name)
+            .map(|d| {
+                d.parse::<NaiveDate>()
+                    .map_err(|_| format!("invalid {}", name))
+            })
+            .transpose()
+    };
+    let days = match (query.get("period"), date("from"), date("to")) {
+        (_, Err(e), _) | (_, _, Err(e)) => return error(400, &e),
+        (Some(period), _, _) => match Period::from_str(period, true) {
+            Ok(period) => period.days(today),
+            Err(_) => return error(400, "invalid period"),
+        },
+        (None, Ok(None), Ok(None)) => today..=today,
+        // Nothing is recorded after today
+        (None, Ok(from), Ok(to)) => {
+            from.unwrap_or(NaiveDate::MIN)..=to.map_or(today, |to| to.min(today))
+        }
+    };
+    if days.is_empty() {
+        return error(400, "from is after to or today");
+    }
+    let group_by = match query.get("group_by").map(|g| GroupBy::from_str(g, true)) {
+        None => GroupBy::default(),
+        Some(Ok(group_by)) => group_by,
+        Some(Err(_)) => return error(400, "invalid group_by"),
+    };
+    let mut history = history.lock().unwrap();
+    if let Some(storage) = storage.filter(|_| group_by == GroupBy::Category) {
+        // Problems are reported by the tracker itself
+        history.load_intervals(storage, &days, today, &mut Vec::new());
+    }
+    let activity = live.activity.lock().unwrap();
+    let today = Today {
+        day: today,
+        totals: &live.window_time,
+        intervals: &activity,
+    };
+    match report::format(
+        &report(&history, &days, &today, group_by),
+        &history.recorded_range(&days, &today),
+        group_by,
+        Format::Json,
+    ) {
+        Ok(body) => (200, body),
+        Err(e) => error(500, &e),
+    }
+}
+
+fn error(status: u16, message: &str) -> (u16, String) {
+    (status, serde_json::json!({ "error": message }).to_string())
+}
+
+// Compares every byte so the time taken doesn't tell how much of the token was right
+fn same_token(given: &str, token: &str) -> bool {
+    given.len() == token.len()
+        && given
+            .bytes()
+            .zip(token.bytes())
+            .fold(0, |difference, (a, b)| difference | (a ^ b))
+            == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    fn live() -> LiveData {
+        let live = LiveData::default();
+        *live.day.lock().unwrap() = NaiveDate::from_ymd_opt(2026, 10, 16).unwrap();
+        live.window_time
+            .insert("editor".to_string(), Duration::from_secs(90));
+        live.input_stats.insert("Mouse move".to_string(), 3);
+        live
+    }
+
+    #[test]
+    fn should_generate_different_tokens() {
+        let token = generate_token().unwrap();
+        assert_eq!(32, token.len());
+        assert!(token.bytes().all(|b| b.is_ascii_hexdigit()));
+        assert_ne!(token, generate_token().unwrap());
+    }
+
+    #[test]
+    fn should_require_the_token() {
+        let live = live();
+        let history = Mutex::new(History::default());
+        let get = |authorization| {
+            handle(
+                &Method::Get,
+                "/api/window_time",
+                authorization,
+                "secret",
+                &live,
+                &history,
+                None,
+            )
+        };
+        assert_eq!(401, get(None).0);
+        assert_eq!(401, get(Some("Bearer wrong")).0);
+        assert_eq!(401, get(Some("secret")).0);
+        assert_eq!(
+            (200, r#"{"editor":90}"#.to_string()),
+            get(Some("Bearer secret"))
+        );
+    }
+
+    #[test]
+    fn should_serve_today_and_history() {
+        let live = live();
+        let mut history = History::default();
+        history.totals.insert(
+            NaiveDate::from_ymd_opt(2026, 10, 15).unwrap(),
+            BTreeMap::from([("editor".to_string(), Duration::from_secs(30))]),
+        );
+        let history = Mutex::new(history);
+        let get = |url| {
+            handle(
+                &Method::Get,
+                url,
+                Some("Bearer t"),
+                "t",
+                &live,
+                &history,
+                None,
+            )
+        };
+        let (status, body) = get("/api/today");
+        assert_eq!(200, status);
+        let today: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!("2026-10-16", today["day"]);
+        assert_eq!(3, today["input_stats"]["Mouse move"]);
+        assert!(today["current"].is_null());
+
+        let (status, body) = get("/api/history?from=2026-10-01&group_by=app");
+        assert_eq!(200, status);
+        let report: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(120, report["rows"][0]["seconds"]);
+        assert_eq!(2, report["rows"][0]["days"]);
+        assert_eq!(400, get("/api/history?from=yesterday").0);
+        assert_eq!(400, get("/api/history?from=2026-10-17").0);
+        assert_eq!(400, get("/api/history?from=2026-10-15&to=2026-10-14").0);
+        let (status, body) = get("/api/history?from=2026-10-15&to=2099-01-01");
+        assert_eq!(200, status);
+        let report: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!("2026-10-16", report["to"]);
+        // Still usable after the rejected requests
+        assert!(history.try_lock().is_ok());
+        assert_eq!(404, get("/api/unknown").0);
+    }
+}
@@ -1,53 +1,117 @@
 use std::{
     cell::RefCell,
+    collections::{BTreeMap, BTreeSet},
+    ops::RangeInclusive,
     rc::Rc,
-    sync::{Arc, Mutex},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
     time::Duration,
 };
 
-use dashmap::DashMap;
+use active_win_pos_rs::ActiveWindow;
+use chrono::{DateTime, Datelike, Local, NaiveDate, NaiveTime, Timelike, Utc, Weekday};
+use chrono_tz::Tz;
 use eframe::egui::{self, Layout, Ui};
-use egui_extras::{Column, TableBuilder};
+use egui_extras::{Column, DatePickerButton, TableBuilder};
 use egui_file_dialog::FileDialog;
-use egui_plot::{BarChart, Plot};
+use egui_plot::{BarChart, Legend, Line, LineStyle, Plot, PlotPoints};
 
-use crate::{utils::generate_file_name, Config, PlotType, INPUT_STATS_FILE};
+use crate::{
+    activity::Interval,
+    api::{generate_token, ApiConfig},
+    calendar::{Calendar, DaySelection, IcsImport},
+    comparison::{compare, Comparison, Delta},
+    export::{export, ExportFormat},
+    heatmap::{Heatmap, HeatmapFilter},
+    history::{AverageMode, DateRange, History, Today},
+    import::{ImportFormat, ImportPlan},
+    open_configured_storage, plan_import,
+    productivity::{Productivity, ProductivityLabels, ProductivitySummary},
+    rules::{Rule, RuleSet},
+    save_all_data,
+    stats::{self, Statistic, Stats},
+    storage::StorageBackend,
+    table::{rows, SortColumn, TableSort},
+    utils::{moving_average, Cached},
+    Config, LiveData, OpenStorage, PlotType,
+};
+
+const STATISTIC_COLUMNS: [(Statistic, &str); 7] = [
+    (Statistic::Avg, "Avg"),
+    (Statistic::Median, "Median"),
+    (Statistic::P95, "P95"),
+    (Statistic::Max, "Max"),
+    (Statistic::StdDev, "Std dev"),
+    (Statistic::DaysUsed, "Days used"),
+    (Statistic::Share, "Share"),
+];
+
+type ProductivityScores =
+    Cached<(RangeInclusive<NaiveDate>, ProductivityLabels), Vec<(NaiveDate, f64)>>;
 
 pub struct TimeBack {
     pub file_dialog: FileDialog,
+    pub ics_dialog: FileDialog,
+    pub new_holiday: NaiveDate,
+    pub calendar_message: Option<String>,
+    pub export_dialog: FileDialog,
+    pub export_open: bool,
+    pub export_format: ExportFormat,
+    pub export_message: Option<String>,
+    pub import_dialog: FileDialog,
+    pub import_open: bool,
+    pub import_format: ImportFormat,
+    pub import_preview: Option<ImportPlan>,
+    pub import_message: Option<String>,
     pub temp_config_path: Option<String>,
-    pub window_time: Arc<DashMap<String, Duration>>,
+    pub live: LiveData,
+    // False when another process records the activity and this window only shows it
+    pub tracking: Arc<AtomicBool>,
+    pub expanded_app: Option<String>,
     pub config: Arc<Mutex<Config>>,
     pub close: Rc<RefCell<bool>>,
     pub show_plot: bool,
     pub plot_type: PlotType,
-    pub graph_data: Vec<Vec<egui_plot::Bar>>,
+    pub history: Arc<Mutex<History>>,
+    // Only read from, for the interval logs of the ranges looked at
+    pub storage: OpenStorage,
+    pub date_range: DateRange,
+    pub custom_from: NaiveDate,
+    pub custom_to: NaiveDate,
+    pub trend_apps: BTreeSet<String>,
+    pub moving_average: bool,
+    pub moving_average_days: usize,
+    pub heatmap_filter: HeatmapFilter,
+    pub app_names: Cached<NaiveDate, BTreeSet<String>>,
+    pub heatmap_categories: Cached<RangeInclusive<NaiveDate>, BTreeSet<String>>,
+    pub heatmap: Cached<(RangeInclusive<NaiveDate>, HeatmapFilter, Option<Tz>), Heatmap>,
+    pub productivity_scores: ProductivityScores,
+    pub show_statistics: bool,
+    pub table_sort: TableSort,
+    pub table_filter: String,
+    pub average_mode: AverageMode,
+    pub day_selection: DaySelection,
     pub settings_open: bool,
     pub input_stats_open: bool,
-    pub input_stats: Arc<DashMap<String, u32>>,
+    pub timeline_open: bool,
+    pub comparison_open: bool,
+    pub comparison: Comparison,
+    pub timeline_day: NaiveDate,
+    pub settings_draft: Config,
+    pub productivity_names: (BTreeSet<String>, BTreeSet<String>),
+    pub warnings: Vec<String>,
 }
 
 impl Drop for TimeBack {
     fn drop(&mut self) {
-        let output_directory = self
-            .config
-            .lock()
-            .map(|config| config.output_directory.clone())
-            .unwrap();
-        if let Some(output_directory) = output_directory {
-            let file_name = generate_file_name();
-            serde_json::to_writer(
-                std::fs::File::create(output_directory.to_owned() + "/" + &file_name)
-                    .unwrap_or_else(|_| panic!("{} file not possible to create", file_name)),
-                &self.window_time,
-            )
-            .unwrap();
-            serde_json::to_writer(
-                std::fs::File::create(output_directory.to_owned() + INPUT_STATS_FILE)
-                    .unwrap_or_else(|_| panic!("{} file not possible to create", file_name)),
-                &self.input_stats,
-            )
-            .unwrap();
+        // The viewer would overwrite the tracker data with what it last read
+        if self.tracking.load(Ordering::SeqCst) {
+            let config = self.config.lock().unwrap().clone();
+            if let Some(storage) = open_configured_storage(&config, true) {
+                save_all_data(&*storage, &self.live);
+            }
         }
     }
 }
@@ -62,23 +126,60 @@ impl eframe::App for TimeBack {
         egui::CentralPanel::default().show(ctx, |ui| {
             ui.horizontal(|ui| {
                 ui.heading("Time back!");
+                self.display_productivity_score(ui);
+                if !self.tracking.load(Ordering::SeqCst) {
+                    ui.label("(viewing the headless tracker)");
+                }
                 ui.with_layout(Layout::right_to_left(egui::Align::Min), |ui| {
                     let config = self.config.lock().map(|config| (*config).clone()).ok();
-                    if let Some(mut config) = config {
+                    if let Some(config) = config {
                         if config.output_directory.is_some() {
                             if ui.button("Settings").clicked() {
                                 self.settings_open = true;
+                                self.settings_draft = config.clone();
+                                self.productivity_names = productivity_names(&config, &self.live);
                             }
                             if ui.button("Input stats").clicked() {
                                 self.input_stats_open = true;
                             }
+                            if ui.button("Export").clicked() {
+                                self.export_open = true;
+                                self.export_message = None;
+                            }
+                            if ui.button("Import").clicked() {
+                                self.import_open = true;
+                                self.import_preview = None;
+                                self.import_message = None;
+                            }
+                            if ui.button("Compare").clicked() {
+                                self.comparison_open = true;
+                            }
+                            if ui.button("Timeline").clicked() {
+                                self.timeline_open = true;
+                                self.timeline_day = *self.live.day.lock().unwrap();
+                            }
                         }
                         if self.settings_open {
-                            self.display_configuration(ctx, &mut config);
+                            self.display_configuration(ctx);
                         }
                         if self.input_stats_open {
                             self.display_input_stats(ctx);
                         }
+                        if self.export_open {
+                            self.display_export(ctx);
+                        }
+                        if self.import_open {
+                            self.display_import(ctx, &config);
+                        }
+                        if self.comparison_open {
+                            self.display_comparison(ctx);
+                        }
+                        if self.timeline_open {
+                            self.display_timeline(ctx, config.timezone());
+                        }
+                        if !self.warnings.is_empty() {
+                            self.display_warnings(ctx);
+                        }
                     }
                     if ui.button("Close").clicked() {
                         *self.close.borrow_mut() = true;
@@ -88,11 +189,7 @@ impl eframe::App for TimeBack {
             });
 
             let configured = if let Ok(config) = self.config.lock() {
-                if config.output_directory.is_none() {
-                    false
-                } else {
-                    true
-                }
+                config.output_directory.is_some()
             } else {
                 false
             };
@@ -110,22 +207,92 @@ impl TimeBack {
     fn display_main_ui(&mut self, ui: &mut Ui) {
         ui.horizontal_top(|ui| {
             ui.vertical(|ui| {
+                ui.horizontal(|ui| {
+                    ui.add(
+                        egui::TextEdit::singleline(&mut self.table_filter)
+                            .hint_text("Filter")
+                            .desired_width(120.),
+                    );
+                    ui.checkbox(&mut self.show_statistics, "Statistics");
+                    if self.show_statistics {
+                        self.display_date_range_selector(ui, "table_date_range");
+                        display_statistics_days_selector(
+                            ui,
+                            &mut self.day_selection,
+                            &mut self.average_mode,
+                            "table_statistics",
+                        );
+                    }
+                });
+                let statistics = if self.show_statistics {
+                    self.range_statistics()
+                } else {
+                    BTreeMap::new()
+                };
+                let statistic_columns = if self.show_statistics {
+                    &STATISTIC_COLUMNS[..]
+                } else {
+                    &[]
+                };
                 let table_height = 20.;
-                let table = TableBuilder::new(ui)
+                let mut table = TableBuilder::new(ui)
                     .striped(true)
                     .resizable(false)
                     .cell_layout(egui::Layout::left_to_right(egui::Align::Center))
                     .column(Column::auto())
                     .column(Column::initial(100.))
+                    .column(Column::auto())
                     .min_scrolled_height(500.0);
+                for _ in statistic_columns {
+                    table = table.column(Column::auto());
+                }
                 if let Ok(config) = self.config.lock() {
+                    let sort = &mut self.table_sort;
+                    let table = table.header(table_height, |mut header| {
+                        for (column, name) in [
+                            (SortColumn::Name, "Application"),
+                            (SortColumn::Duration, "Today"),
+                            (SortColumn::Share, "Today %"),
+                        ] {
+                            header.col(|ui| {
+                                let label = format!("{}{}", name, sort.arrow(column));
+                                if ui
+                                    .add(
+                                        egui::Button::new(egui::RichText::new(label).strong())
+                                            .frame(false),
+                                    )
+                                    .clicked()
+                                {
+                                    sort.toggle(column);
+                                }
+                            });
+                        }
+                        for (_, name) in statistic_columns {
+                            header.col(|ui| {
+                                ui.strong(*name);
+                            });
+                        }
+                    });
                     table.body(|mut body| {
-                        let mut overall = Duration::new(0, 0);
-                        for v in self.window_time.iter() {
-                            let (n, d) = v.pair();
+                        let overall = self
+                            .live
+                            .window_time
+                            .iter()
+                            .map(|v| *v.value())
+                            .sum::<Duration>();
+                        for usage in
+                            rows(&self.live.window_time, self.table_sort, &self.table_filter)
+                        {
+                            let (n, d, share) = (&usage.name, &usage.duration, usage.share);
                             let mut checked = config.processes_with_longer_tracking.contains(n);
+                            let expanded = self.expanded_app.as_deref() == Some(n.as_str());
                             body.row(table_height, |mut row| {
                                 row.col(|ui| {
+                                    if ui.small_button(if expanded { "▼" } else { "▶" }).clicked()
+                                    {
+                                        self.expanded_app =
+                                            if expanded { None } else { Some(n.to_string()) };
+                                    }
                                     if ui.checkbox(&mut checked, n).clicked() {
                                         if checked {
                                             config
@@ -145,12 +312,62 @@ impl TimeBack {
                                 row.col(|ui| {
                                     ui.label(humantime::Duration::from(*d).to_string());
                                 });
-                                overall += *d;
-                            })
+                                row.col(|ui| {
+                                    ui.label(format!("{:.1}%", share));
+                                });
+                                let stats = statistics.get(n);
+                                for (statistic, _) in statistic_columns {
+                                    row.col(|ui| {
+                                        if let Some(stats) = stats {
+                                            ui.label(format_statistic(
+                                                *statistic,
+                                                stats.get(*statistic),
+                                            ));
+                                        }
+                                    });
+                                }
+                            });
+                            if expanded {
+                                let mut titles = self
+                                    .live
+                                    .window_titles
+                                    .get(n)
+                                    .map(|titles| {
+                                        titles
+                                            .iter()
+                                            .map(|t| (t.key().to_string(), *t.value()))
+                                            .collect::<Vec<_>>()
+                                    })
+                                    .unwrap_or_default();
+                                titles.sort_by_key(|t| std::cmp::Reverse(t.1));
+                                for (title, d) in titles {
+                                    body.row(table_height, |mut row| {
+                                        row.col(|ui| {
+                                            ui.add_space(30.);
+                                            ui.label(if title.is_empty() {
+                                                "<no title>"
+                                            } else {
+                                                &title
+                                            });
+                                        });
+                                        row.col(|ui| {
+                                            ui.label(humantime::Duration::from(d).to_string());
+                                        });
+                                        row.col(|_ui| {});
+                                        for _ in statistic_columns {
+                                            row.col(|_ui| {});
+                                        }
+                                    });
+                                }
+                            }
                         }
                         body.row(table_height, |mut row| {
                             row.col(|_ui| {});
                             row.col(|_ui| {});
+                            row.col(|_ui| {});
+                            for _ in statistic_columns {
+                                row.col(|_ui| {});
+                            }
                         });
                         body.row(table_height, |mut row| {
                             row.col(|ui| {
@@ -159,6 +376,10 @@ impl TimeBack {
                             row.col(|ui| {
                                 ui.label(humantime::Duration::from(overall).to_string());
                             });
+                            row.col(|_ui| {});
+                            for _ in statistic_columns {
+                                row.col(|_ui| {});
+                            }
                         })
                     });
                 }
@@ -168,20 +389,87 @@ impl TimeBack {
                     self.show_plot = !self.show_plot;
                 }
                 if self.show_plot {
-                    ui.label("Only live graph includes today data");
-                    ui.horizontal(|ui| {
+                    ui.horizontal_wrapped(|ui| {
                         ui.radio_value(&mut self.plot_type, PlotType::Live, "Live");
                         ui.radio_value(&mut self.plot_type, PlotType::Sum, "Sum");
                         ui.radio_value(&mut self.plot_type, PlotType::Avg, "Avg");
                         ui.radio_value(&mut self.plot_type, PlotType::Median, "Median");
+                        ui.radio_value(&mut self.plot_type, PlotType::Min, "Min");
+                        ui.radio_value(&mut self.plot_type, PlotType::Max, "Max");
+                        ui.radio_value(&mut self.plot_type, PlotType::P90, "P90");
+                        ui.radio_value(&mut self.plot_type, PlotType::P95, "P95");
+                        ui.radio_value(&mut self.plot_type, PlotType::StdDev, "Std dev");
+                        ui.radio_value(&mut self.plot_type, PlotType::DaysUsed, "Days used");
+                        ui.radio_value(&mut self.plot_type, PlotType::Share, "Share");
+                        ui.radio_value(&mut self.plot_type, PlotType::Productivity, "Productivity");
+                        ui.radio_value(&mut self.plot_type, PlotType::Trend, "Trend");
+                        ui.radio_value(&mut self.plot_type, PlotType::Heatmap, "Heatmap");
                     });
+                    if self.plot_type != PlotType::Live {
+                        self.display_date_range_selector(ui, "plot_date_range");
+                    }
+                    if self.plot_type.statistic().is_some() {
+                        display_statistics_days_selector(
+                            ui,
+                            &mut self.day_selection,
+                            &mut self.average_mode,
+                            "plot_statistics",
+                        );
+                    }
+                    let today = *self.live.day.lock().unwrap();
+                    let days = self
+                        .date_range
+                        .days(today, &(self.custom_from..=self.custom_to));
+                    if matches!(self.plot_type, PlotType::Productivity | PlotType::Heatmap) {
+                        self.load_intervals(&days);
+                    }
+                    let history = self.history.clone();
+                    let history = history.lock().unwrap();
+                    let activity = self.live.activity.lock().unwrap();
+                    let today = Today {
+                        day: today,
+                        totals: &self.live.window_time,
+                        intervals: &activity,
+                    };
+                    if self.plot_type == PlotType::Trend {
+                        display_trend_selector(
+                            ui,
+                            &mut self.trend_apps,
+                            &mut self.moving_average,
+                            &mut self.moving_average_days,
+                            self.app_names.get(today.day, || history.app_names(&today)),
+                        );
+                    }
+                    if self.plot_type == PlotType::Heatmap {
+                        let app_names = self.app_names.get(today.day, || history.app_names(&today));
+                        let categories = self.heatmap_categories.get(days.clone(), || {
+                            history
+                                .intervals(&days, &today)
+                                .filter_map(|i| i.category.clone())
+                                .collect()
+                        });
+                        display_heatmap_filter(ui, &mut self.heatmap_filter, app_names, categories);
+                    }
                     ui.add_space(5.);
-                    Plot::new("Sum").show(ui, |plot_ui| {
-                        plot_ui.bar_chart(BarChart::new(match self.plot_type {
-                            PlotType::Sum => self.graph_data[PlotType::Sum as usize].clone(),
-                            PlotType::Avg => self.graph_data[PlotType::Avg as usize].clone(),
-                            PlotType::Median => self.graph_data[PlotType::Median as usize].clone(),
-                            PlotType::Live => self
+                    let calendar = self.config.lock().unwrap().calendar.clone();
+                    let bars = match self.plot_type {
+                        PlotType::Productivity => {
+                            let labels = self.config.lock().unwrap().productivity.clone();
+                            Some(
+                                self.productivity_scores
+                                    .get((days.clone(), labels.clone()), || {
+                                        history.productivity_scores(&days, &labels, &today)
+                                    })
+                                    .iter()
+                                    .enumerate()
+                                    .map(|(i, (day, score))| {
+                                        egui_plot::Bar::new(i as f64, *score).name(day)
+                                    })
+                                    .collect(),
+                            )
+                        }
+                        PlotType::Live => Some(
+                            self.live
                                 .window_time
                                 .iter()
                                 .enumerate()
@@ -190,14 +478,127 @@ impl TimeBack {
                                     egui_plot::Bar::new(i as f64, v.as_secs_f64()).name(k)
                                 })
                                 .collect(),
-                        }));
-                    });
+                        ),
+                        PlotType::Trend | PlotType::Heatmap => None,
+                        _ => self.plot_type.statistic().map(|statistic| {
+                            to_bars(stats::rank(
+                                &history.durations(&days, &today, self.average_mode, |day| {
+                                    self.day_selection.includes(day, &calendar)
+                                }),
+                                statistic,
+                            ))
+                        }),
+                    };
+                    match bars {
+                        Some(bars) => {
+                            Plot::new("Sum").show(ui, |plot_ui| {
+                                plot_ui.bar_chart(BarChart::new(bars));
+                            });
+                        }
+                        None if self.plot_type == PlotType::Heatmap => {
+                            let timezone = self.config.lock().unwrap().timezone();
+                            let filter = &self.heatmap_filter;
+                            display_heatmap(
+                                ui,
+                                self.heatmap
+                                    .get((days.clone(), filter.clone(), timezone), || {
+                                        Heatmap::new(
+                                            history.intervals(&days, &today),
+                                            filter,
+                                            timezone,
+                                        )
+                                    }),
+                            );
+                        }
+                        None => {
+                            let lines = self
+                                .trend_apps
+                                .iter()
+                                .map(|app| (app, history.daily_durations(&days, app, &today)));
+                            display_trend_plot(
+                                ui,
+                                lines,
+                                self.moving_average.then_some(self.moving_average_days),
+                            );
+                        }
+                    }
                 }
             });
         });
     }
 
-    fn display_configuration(&mut self, ctx: &egui::Context, config: &mut Config) {
+    // Statistics of the daily time of each application over the selected range
+    fn range_statistics(&self) -> BTreeMap<String, Stats> {
+        let calendar = self.config.lock().unwrap().calendar.clone();
+        let today = *self.live.day.lock().unwrap();
+        let days = self
+            .date_range
+            .days(today, &(self.custom_from..=self.custom_to));
+        let history = self.history.lock().unwrap();
+        let activity = self.live.activity.lock().unwrap();
+        let durations = history.durations(
+            &days,
+            &Today {
+                day: today,
+                totals: &self.live.window_time,
+                intervals: &activity,
+            },
+            self.average_mode,
+            |day| self.day_selection.includes(day, &calendar),
+        );
+        stats::calculate(&durations)
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v))
+            .collect()
+    }
+
+    fn display_date_range_selector(&mut self, ui: &mut Ui, id: &str) {
+        ui.horizontal(|ui| {
+            egui::ComboBox::from_id_salt(id)
+                .selected_text(self.date_range.label())
+                .show_ui(ui, |ui| {
+                    for range in DateRange::ALL {
+                        ui.selectable_value(&mut self.date_range, range, range.label());
+                    }
+                });
+            if self.date_range == DateRange::Custom {
+                ui.add(
+                    DatePickerButton::new(&mut self.custom_from).id_salt(&format!("{}_from", id)),
+                );
+                ui.label("to");
+                ui.add(DatePickerButton::new(&mut self.custom_to).id_salt(&format!("{}_to", id)));
+                if self.custom_from > self.custom_to {
+                    std::mem::swap(&mut self.custom_from, &mut self.custom_to);
+                }
+            }
+        });
+    }
+
+    fn display_productivity_score(&self, ui: &mut Ui) {
+        let Ok(config) = self.config.lock() else {
+            return;
+        };
+        let activity = self.live.activity.lock().unwrap();
+        let summary = ProductivitySummary::new(
+            &config.productivity,
+            activity
+                .iter()
+                .filter(|i| i.active)
+                .map(|i| (i.app_name.as_str(), i.category.as_deref(), i.duration())),
+        );
+        if let Some(score) = summary.score() {
+            ui.label(format!("Productivity: {:.0}", score))
+                .on_hover_text(format!(
+                    "Productive: {}\nNeutral: {}\nDistracting: {}",
+                    humantime::Duration::from(Duration::from_secs(summary.productive.as_secs())),
+                    humantime::Duration::from(Duration::from_secs(summary.neutral.as_secs())),
+                    humantime::Duration::from(Duration::from_secs(summary.distracting.as_secs())),
+                ));
+        }
+    }
+
+    fn display_configuration(&mut self, ctx: &egui::Context) {
+        let config = &mut self.settings_draft;
         egui::Window::new("Settings")
             .open(&mut self.settings_open)
             .resizable(false)
@@ -208,13 +609,59 @@ impl TimeBack {
                 self.file_dialog.update(ctx);
 
                 if let Some(path) = self.file_dialog.take_picked() {
-                    config.output_directory =
-                        path.to_path_buf().into_os_string().into_string().ok();
+                    self.temp_config_path = path.to_path_buf().into_os_string().into_string().ok();
                 }
                 ui.label(format!(
                     "Current output directory: {:?}",
-                    config.output_directory.as_ref().map_or("", |d| &d)
+                    self.temp_config_path
+                        .as_ref()
+                        .or(config.output_directory.as_ref())
+                        .map_or("", |d| d)
                 ));
+                ui.horizontal(|ui| {
+                    ui.label("Storage");
+                    ui.radio_value(&mut config.storage, StorageBackend::Json, "JSON files");
+                    ui.radio_value(&mut config.storage, StorageBackend::Sqlite, "SQLite");
+                });
+                ui.separator();
+                ui.heading("Day boundaries");
+                display_day_boundaries_editor(ui, config);
+                ui.separator();
+                ui.heading("Work calendar");
+                display_calendar_editor(ui, &mut config.calendar, &mut self.new_holiday);
+                ui.horizontal(|ui| {
+                    if ui.button("Import holidays from .ics").clicked() {
+                        self.ics_dialog.pick_file();
+                    }
+                    if let Some(message) = &self.calendar_message {
+                        ui.label(message);
+                    }
+                });
+                self.ics_dialog.update(ctx);
+                if let Some(path) = self.ics_dialog.take_picked() {
+                    let imported = std::fs::read_to_string(&path)
+                        .map_err(|e| e.to_string())
+                        .and_then(|content| {
+                            let today = config.current_day();
+                            config
+                                .calendar
+                                .import_ics(&content, config.timezone(), today)
+                        });
+                    self.calendar_message = Some(match imported {
+                        Ok(IcsImport {
+                            added,
+                            ignored_rules: 0,
+                        }) => format!("{} holidays imported", added),
+                        Ok(IcsImport {
+                            added,
+                            ignored_rules,
+                        }) => format!(
+                            "{} holidays imported, only the first day of {} repeating events: their rule is not supported",
+                            added, ignored_rules
+                        ),
+                        Err(e) => format!("Unable to import {:?}: {}", path, e),
+                    });
+                }
                 ui.separator();
                 ui.heading("Long tracking processes");
                 ui.horizontal(|ui| {
@@ -224,10 +671,27 @@ impl TimeBack {
                     }
                 });
                 ui.separator();
+                ui.heading("Project rules");
+                let current_window = self.live.current_window.lock().unwrap().clone();
+                display_rules_editor(ui, &mut config.rules, &current_window);
+                ui.separator();
+                ui.heading("Productivity");
+                let (apps, categories) = &self.productivity_names;
+                display_productivity_editor(ui, &mut config.productivity, apps, categories);
+                ui.separator();
+                ui.heading("HTTP API");
+                display_api_editor(ui, &mut config.api);
+                ui.separator();
                 if ui.button("Accept").clicked() {
                     if self.temp_config_path.is_some() {
                         config.output_directory = self.temp_config_path.clone();
                     }
+                    if let Ok(mut shared_config) = self.config.lock() {
+                        // Edited from the main table while the settings are open
+                        config.processes_with_longer_tracking =
+                            shared_config.processes_with_longer_tracking.clone();
+                        *shared_config = config.clone();
+                    }
                     match confy::store("time_back", None, &*config) {
                         Ok(_) => {}
                         Err(_) => {
@@ -238,8 +702,256 @@ impl TimeBack {
             });
     }
 
+    fn display_warnings(&mut self, ctx: &egui::Context) {
+        egui::Window::new("Data problems")
+            .resizable(true)
+            .show(ctx, |ui| {
+                for warning in &self.warnings {
+                    ui.colored_label(ui.visuals().warn_fg_color, warning);
+                }
+                if ui.button("Dismiss").clicked() {
+                    self.warnings.clear();
+                }
+            });
+    }
+
+    fn display_export(&mut self, ctx: &egui::Context) {
+        let mut open = self.export_open;
+        egui::Window::new("Export")
+            .open(&mut open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Format");
+                    for format in ExportFormat::ALL {
+                        ui.radio_value(&mut self.export_format, format, format.label());
+                    }
+                });
+                self.display_date_range_selector(ui, "export_date_range");
+                ui.horizontal(|ui| {
+                    if ui.button("Save as…").clicked() {
+                        self.export_dialog.config_mut().default_file_name =
+                            format!("time_back.{}", self.export_format.extension());
+                        self.export_dialog.save_file();
+                    }
+                    if let Some(message) = &self.export_message {
+                        ui.label(message);
+                    }
+                });
+            });
+        self.export_open = open;
+        self.export_dialog.update(ctx);
+        if let Some(path) = self.export_dialog.take_picked() {
+            let today = *self.live.day.lock().unwrap();
+            let days = self
+                .date_range
+                .days(today, &(self.custom_from..=self.custom_to));
+            self.load_intervals(&days);
+            let exported = {
+                let history = self.history.lock().unwrap();
+                let activity = self.live.activity.lock().unwrap();
+                let today = Today {
+                    day: today,
+                    totals: &self.live.window_time,
+                    intervals: &activity,
+                };
+                export(&history, &days, &today, self.export_format)
+            };
+            self.export_message = Some(
+                match exported
+                    .and_then(|content| std::fs::write(&path, content).map_err(|e| e.to_string()))
+                {
+                    Ok(()) => format!("Exported to {:?}", path),
+                    Err(e) => format!("Unable to export to {:?}: {}", path, e),
+                },
+            );
+        }
+    }
+
+    fn display_import(&mut self, ctx: &egui::Context, config: &Config) {
+        let mut apply = false;
+        egui::Window::new("Import")
+            .open(&mut self.import_open)
+            .resizable(true)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Format");
+                    for format in ImportFormat::ALL {
+                        ui.radio_value(&mut self.import_format, format, format.label());
+                    }
+                });
+                if ui.button("Choose file…").clicked() {
+                    self.import_dialog.pick_file();
+                }
+                if let Some(plan) = &self.import_preview {
+                    egui::ScrollArea::vertical()
+                        .max_height(300.)
+                        .show(ui, |ui| {
+                            ui.monospace(plan.summary());
+                        });
+                    ui.horizontal(|ui| {
+                        apply = ui.button("Import").clicked();
+                        if ui.button("Cancel").clicked() {
+                            self.import_preview = None;
+                        }
+                    });
+                }
+                if let Some(message) = &self.import_message {
+                    ui.label(message);
+                }
+            });
+        self.import_dialog.update(ctx);
+        let picked = self.import_dialog.take_picked();
+        if picked.is_none() && !apply {
+            return;
+        }
+        let Some(storage) = open_configured_storage(config, self.tracking.load(Ordering::SeqCst))
+        else {
+            self.import_message = Some("Unable to open the storage".to_string());
+            return;
+        };
+        if let Some(path) = picked {
+            match plan_import(
+                config,
+                &*storage,
+                &path,
+                self.import_format,
+                &mut self.warnings,
+            ) {
+                Ok(plan) => {
+                    self.import_preview = Some(plan);
+                    self.import_message = None;
+                }
+                Err(e) => {
+                    self.import_preview = None;
+                    self.import_message = Some(format!("Unable to import {:?}: {}", path, e));
+                }
+            }
+        } else if let Some(plan) = self.import_preview.take() {
+            let imported = plan.apply(&*storage, &mut self.warnings);
+            // The imported days are in the past, so only the history changed
+            let today = *self.live.day.lock().unwrap();
+            *self.history.lock().unwrap() = History::load(&*storage, today, &mut self.warnings);
+            self.import_message = Some(format!("{} days imported", imported));
+        }
+    }
+
+    // The interval logs are only read for the views that need them
+    fn load_intervals(&mut self, days: &RangeInclusive<NaiveDate>) {
+        if self.history.lock().unwrap().has_intervals(days) {
+            return;
+        }
+        let today = *self.live.day.lock().unwrap();
+        let config = self.config.lock().unwrap().clone();
+        if let Some(storage) = self.storage.get(&config) {
+            self.history
+                .lock()
+                .unwrap()
+                .load_intervals(storage, days, today, &mut self.warnings);
+        }
+    }
+
+    fn display_comparison(&mut self, ctx: &egui::Context) {
+        let today = *self.live.day.lock().unwrap();
+        let (before, after) = self.comparison.periods(today);
+        self.load_intervals(&before);
+        self.load_intervals(&after);
+        let (apps, categories) = {
+            let history = self.history.lock().unwrap();
+            let activity = self.live.activity.lock().unwrap();
+            let today = Today {
+                day: today,
+                totals: &self.live.window_time,
+                intervals: &activity,
+            };
+            (
+                compare(
+                    &history.app_totals(&before, &today),
+                    &history.app_totals(&after, &today),
+                ),
+                compare(
+                    &history.category_totals(&before, &today),
+                    &history.category_totals(&after, &today),
+                ),
+            )
+        };
+        egui::Window::new("Compare")
+            .open(&mut self.comparison_open)
+            .resizable(true)
+            .show(ctx, |ui| {
+                egui::ComboBox::from_id_salt("comparison")
+                    .selected_text(self.comparison.label())
+                    .show_ui(ui, |ui| {
+                        for c in Comparison::ALL {
+                            ui.selectable_value(&mut self.comparison, c, c.label());
+                        }
+                    });
+                let format_period = |p: &RangeInclusive<NaiveDate>| {
+                    format!(
+                        "{} - {}",
+                        p.start().format("%b %d"),
+                        p.end().format("%b %d")
+                    )
+                };
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    ui.heading("Categories");
+                    display_deltas(
+                        ui,
+                        "category_deltas",
+                        &categories,
+                        &before,
+                        &after,
+                        format_period,
+                    );
+                    ui.heading("Applications");
+                    display_deltas(ui, "app_deltas", &apps, &before, &after, format_period);
+                });
+            });
+    }
+
+    fn display_timeline(&mut self, ctx: &egui::Context, timezone: Option<Tz>) {
+        let today = *self.live.day.lock().unwrap();
+        let intervals = if self.timeline_day == today {
+            self.live.activity.lock().unwrap().clone()
+        } else {
+            self.load_intervals(&(self.timeline_day..=self.timeline_day));
+            self.history
+                .lock()
+                .unwrap()
+                .intervals
+                .get(&self.timeline_day)
+                .cloned()
+                .unwrap_or_default()
+        };
+        egui::Window::new("Timeline")
+            .open(&mut self.timeline_open)
+            .resizable(true)
+            .default_width(700.)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    if ui.button("◀").clicked() {
+                        self.timeline_day =
+                            self.timeline_day.pred_opt().unwrap_or(self.timeline_day);
+                    }
+                    ui.add(DatePickerButton::new(&mut self.timeline_day).id_salt("timeline_day"));
+                    if ui
+                        .add_enabled(self.timeline_day < today, egui::Button::new("▶"))
+                        .clicked()
+                    {
+                        self.timeline_day = self.timeline_day.succ_opt().unwrap_or(today);
+                    }
+                });
+                if intervals.is_empty() {
+                    ui.label("Nothing recorded on this day");
+                } else {
+                    display_timeline_chart(ui, &intervals, timezone);
+                }
+            });
+    }
+
     fn display_input_stats(&mut self, ctx: &egui::Context) {
         let mut data: Vec<(String, u32)> = self
+            .live
             .input_stats
             .iter()
             .map(|v| {
@@ -247,7 +959,7 @@ impl TimeBack {
                 (k.to_string(), *v)
             })
             .collect::<Vec<_>>();
-        data.sort_by(|a, b| b.1.cmp(&a.1));
+        data.sort_by_key(|v| std::cmp::Reverse(v.1));
         egui::Window::new("Input stats")
             .open(&mut self.input_stats_open)
             .resizable(true)
@@ -313,7 +1025,7 @@ impl TimeBack {
         if let Ok(config) = self.config.lock() {
             ui.label(format!(
                 "Current output directory: {:?}",
-                config.output_directory.as_ref().map_or("", |d| &d)
+                config.output_directory.as_ref().map_or("", |d| d)
             ));
         }
         if ui.button("Accept").clicked() {
@@ -331,3 +1043,621 @@ impl TimeBack {
         }
     }
 }
+
+fn display_rules_editor(ui: &mut Ui, rules: &mut Vec<Rule>, current_window: &ActiveWindow) {
+    ui.label("Rules are checked in order, the first one matching assigns project and category. Empty patterns match anything.");
+    let rule_set = RuleSet::new(rules);
+    let mut move_up = None;
+    let mut remove = None;
+    egui::Grid::new("rules").striped(true).show(ui, |ui| {
+        ui.label("App regex");
+        ui.label("Title regex");
+        ui.label("Path regex");
+        ui.label("Project");
+        ui.label("Category");
+        ui.end_row();
+        for (i, rule) in rules.iter_mut().enumerate() {
+            ui.add(egui::TextEdit::singleline(&mut rule.app_name).desired_width(100.));
+            ui.add(egui::TextEdit::singleline(&mut rule.title).desired_width(100.));
+            ui.add(egui::TextEdit::singleline(&mut rule.process_path).desired_width(100.));
+            ui.add(egui::TextEdit::singleline(&mut rule.project).desired_width(80.));
+            ui.add(egui::TextEdit::singleline(&mut rule.category).desired_width(80.));
+            ui.horizontal(|ui| {
+                if ui.add_enabled(i > 0, egui::Button::new("⬆")).clicked() {
+                    move_up = Some(i);
+                }
+                if ui.button("🗑").clicked() {
+                    remove = Some(i);
+                }
+            });
+            ui.end_row();
+        }
+    });
+    if let Some(i) = move_up {
+        rules.swap(i - 1, i);
+    }
+    if let Some(i) = remove {
+        rules.remove(i);
+    }
+    if ui.button("Add rule").clicked() {
+        rules.push(Rule::default());
+    }
+    for (i, error) in &rule_set.errors {
+        ui.colored_label(
+            ui.visuals().error_fg_color,
+            format!("Rule {} is invalid: {}", i + 1, error),
+        );
+    }
+
+    ui.add_space(5.);
+    let process_path = current_window.process_path.to_string_lossy();
+    ui.label(format!(
+        "Current window: {} - {:?} ({})",
+        current_window.app_name, current_window.title, process_path
+    ));
+    match rule_set.find_match(
+        &current_window.app_name,
+        &current_window.title,
+        &process_path,
+    ) {
+        Some((i, rule)) => ui.label(format!(
+            "Matched rule {}: project {:?}, category {:?}",
+            i + 1,
+            rule.project,
+            rule.category
+        )),
+        None => ui.label("No rule matches the current window"),
+    };
+}
+
+fn display_productivity_editor(
+    ui: &mut Ui,
+    labels: &mut ProductivityLabels,
+    apps: &BTreeSet<String>,
+    categories: &BTreeSet<String>,
+) {
+    ui.label("Neutral is the default for apps and categories without a label. Category labels win over app labels.");
+    egui::ScrollArea::vertical()
+        .max_height(200.)
+        .show(ui, |ui| {
+            egui::Grid::new("productivity")
+                .striped(true)
+                .show(ui, |ui| {
+                    for category in categories {
+                        ui.label(format!("Category: {}", category));
+                        productivity_selector(ui, &mut labels.categories, category.clone());
+                        ui.end_row();
+                    }
+                    for app in apps {
+                        ui.label(format!("App: {}", app));
+                        productivity_selector(ui, &mut labels.apps, app.clone());
+                        ui.end_row();
+                    }
+                });
+        });
+}
+
+// Listed once when the settings open, so an app stays in the list after its label is
+// removed: the apps of today and the categories of the rules, plus the labelled ones
+fn productivity_names(config: &Config, live: &LiveData) -> (BTreeSet<String>, BTreeSet<String>) {
+    let apps = live
+        .window_time
+        .iter()
+        .map(|v| v.key().clone())
+        .chain(config.productivity.apps.keys().cloned())
+        .collect();
+    let categories = config
+        .rules
+        .iter()
+        .map(|r| r.category.clone())
+        .filter(|c| !c.is_empty())
+        .chain(config.productivity.categories.keys().cloned())
+        .collect();
+    (apps, categories)
+}
+
+fn productivity_selector(ui: &mut Ui, labels: &mut BTreeMap<String, Productivity>, name: String) {
+    let mut productivity = labels.get(&name).copied().unwrap_or_default();
+    ui.horizontal(|ui| {
+        ui.radio_value(&mut productivity, Productivity::Productive, "Productive");
+        ui.radio_value(&mut productivity, Productivity::Neutral, "Neutral");
+        ui.radio_value(&mut productivity, Productivity::Distracting, "Distracting");
+    });
+    if productivity == Productivity::Neutral {
+        labels.remove(&name);
+    } else {
+        labels.insert(name, productivity);
+    }
+}
+
+fn display_day_boundaries_editor(ui: &mut Ui, config: &mut Config) {
+    ui.horizontal(|ui| {
+        ui.label("Day starts at");
+        let mut hour = config.day_start.hour();
+        let mut minute = config.day_start.minute();
+        let hour_changed = ui
+            .add(egui::DragValue::new(&mut hour).range(0..=23))
+            .changed();
+        ui.label(":");
+        let minute_changed = ui
+            .add(egui::DragValue::new(&mut minute).range(0..=59))
+            .changed();
+        if hour_changed || minute_changed {
+            config.day_start = NaiveTime::from_hms_opt(hour, minute, 0).unwrap_or_default();
+        }
+    });
+    ui.horizontal(|ui| {
+        ui.label("Timezone");
+        let mut timezone = config.timezone.clone().unwrap_or_default();
+        if ui
+            .add(egui::TextEdit::singleline(&mut timezone).hint_text("System timezone"))
+            .changed()
+        {
+            config.timezone = Some(timezone.trim().to_string()).filter(|tz| !tz.is_empty());
+        }
+    });
+    if config.timezone.is_some() && config.timezone().is_none() {
+        ui.colored_label(
+            ui.visuals().error_fg_color,
+            "Unknown timezone, use an IANA name like Europe/Rome. The system timezone is used meanwhile.",
+        );
+    }
+}
+
+fn display_api_editor(ui: &mut Ui, api: &mut ApiConfig) {
+    if ui
+        .checkbox(&mut api.enabled, "Serve the data on localhost")
+        .changed()
+        && api.token.is_empty()
+    {
+        api.token = generate_token().unwrap_or_default();
+    }
+    ui.horizontal(|ui| {
+        ui.label("Port");
+        ui.add(egui::DragValue::new(&mut api.port).range(1024..=65535));
+        ui.label("Token");
+        ui.add(egui::TextEdit::singleline(&mut api.token).desired_width(260.));
+        if ui.button("Generate").clicked() {
+            if let Ok(token) = generate_token() {
+                api.token = token;
+            }
+        }
+    });
+    if api.enabled && api.token.is_empty() {
+        ui.colored_label(ui.visuals().error_fg_color, "The API needs a token");
+    }
+    ui.label("Applies after restarting Time back!");
+}
+
+fn to_bars(values: Vec<(&str, f64)>) -> Vec<egui_plot::Bar> {
+    values
+        .into_iter()
+        .enumerate()
+        .map(|(i, (k, v))| egui_plot::Bar::new(i as f64, v).name(k))
+        .collect()
+}
+
+fn display_trend_selector(
+    ui: &mut Ui,
+    selected: &mut BTreeSet<String>,
+    moving_average: &mut bool,
+    moving_average_days: &mut usize,
+    apps: &BTreeSet<String>,
+) {
+    ui.horizontal(|ui| {
+        egui::ComboBox::from_id_salt("trend_apps")
+            .selected_text(match selected.len() {
+                0 => "Select applications".to_string(),
+                1 => selected.iter().next().cloned().unwrap_or_default(),
+                n => format!("{} applications", n),
+            })
+            .show_ui(ui, |ui| {
+                for app in apps {
+                    let mut checked = selected.contains(app);
+                    if ui.checkbox(&mut checked, app).changed() {
+                        if checked {
+                            selected.insert(app.clone());
+                        } else {
+                            selected.remove(app);
+                        }
+                    }
+                }
+            });
+        ui.checkbox(moving_average, "Moving average");
+        if *moving_average {
+            ui.add(
+                egui::DragValue::new(moving_average_days)
+                    .range(2..=90)
+                    .suffix(" days"),
+            );
+        }
+    });
+}
+
+// Days are on the x axis as days from the common era, the time is in hours
+fn display_trend_plot<'a>(
+    ui: &mut Ui,
+    lines: impl Iterator<Item = (&'a String, Vec<(NaiveDate, Duration)>)>,
+    moving_average_days: Option<usize>,
+) {
+    let to_date = |x: f64| NaiveDate::from_num_days_from_ce_opt(x.round() as i32);
+    Plot::new("Trend")
+        .legend(Legend::default())
+        .y_axis_label("hours")
+        .x_axis_formatter(move |mark, _| {
+            to_date(mark.value).map_or(String::new(), |d| d.to_string())
+        })
+        .label_formatter(move |name, point| {
+            format!(
+                "{}\n{}\n{:.2} hours",
+                name,
+                to_date(point.x).map_or(String::new(), |d| d.to_string()),
+                point.y
+            )
+        })
+        .show(ui, |plot_ui| {
+            for (app, days) in lines {
+                let x = days
+                    .iter()
+                    .map(|(day, _)| day.num_days_from_ce() as f64)
+                    .collect::<Vec<_>>();
+                let hours = days
+                    .iter()
+                    .map(|(_, d)| d.as_secs_f64() / 3600.)
+                    .collect::<Vec<_>>();
+                plot_ui.line(
+                    Line::new(
+                        x.iter()
+                            .zip(&hours)
+                            .map(|(x, y)| [*x, *y])
+                            .collect::<PlotPoints>(),
+                    )
+                    .name(app),
+                );
+                if let Some(window) = moving_average_days {
+                    let average = moving_average(&hours, window);
+                    plot_ui.line(
+                        Line::new(
+                            x.iter()
+                                .zip(&average)
+                                .map(|(x, y)| [*x, *y])
+                                .collect::<PlotPoints>(),
+                        )
+                        .style(LineStyle::dashed_loose())
+                        .name(format!("{} ({} days average)", app, window)),
+                    );
+                }
+            }
+        });
+}
+
+fn display_heatmap_filter(
+    ui: &mut Ui,
+    filter: &mut HeatmapFilter,
+    apps: &BTreeSet<String>,
+    categories: &BTreeSet<String>,
+) {
+    egui::ComboBox::from_id_salt("heatmap_filter")
+        .selected_text(match filter {
+            HeatmapFilter::All => "All applications".to_string(),
+            HeatmapFilter::App(app) => app.clone(),
+            HeatmapFilter::Category(category) => format!("Category: {}", category),
+        })
+        .show_ui(ui, |ui| {
+            ui.selectable_value(filter, HeatmapFilter::All, "All applications");
+            for category in categories {
+                let label = format!("Category: {}", category);
+                ui.selectable_value(filter, HeatmapFilter::Category(category.clone()), label);
+            }
+            for app in apps {
+                ui.selectable_value(filter, HeatmapFilter::App(app.clone()), app);
+            }
+        });
+}
+
+fn display_heatmap(ui: &mut Ui, heatmap: &Heatmap) {
+    const DAYS: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+    let label_width = 40.;
+    let label_height = 20.;
+    let available = ui.available_size();
+    let cell = ((available.x - label_width) / 24.)
+        .min((available.y - label_height) / 7.)
+        .max(8.);
+    let (response, painter) = ui.allocate_painter(
+        egui::vec2(label_width + cell * 24., label_height + cell * 7.),
+        egui::Sense::hover(),
+    );
+    let origin = response.rect.min + egui::vec2(label_width, label_height);
+    let text_color = ui.visuals().text_color();
+    let font = egui::FontId::proportional(10.);
+    for hour in (0..24).step_by(3) {
+        painter.text(
+            origin + egui::vec2(hour as f32 * cell, -2.),
+            egui::Align2::LEFT_BOTTOM,
+            hour,
+            font.clone(),
+            text_color,
+        );
+    }
+    let max = heatmap.max();
+    let hot = ui.visuals().selection.bg_fill;
+    let cold = ui.visuals().extreme_bg_color;
+    for (day, hours) in heatmap.minutes.iter().enumerate() {
+        painter.text(
+            origin + egui::vec2(-4., (day as f32 + 0.5) * cell),
+            egui::Align2::RIGHT_CENTER,
+            DAYS[day],
+            font.clone(),
+            text_color,
+        );
+        for (hour, minutes) in hours.iter().enumerate() {
+            let rect = egui::Rect::from_min_size(
+                origin + egui::vec2(hour as f32 * cell, day as f32 * cell),
+                egui::vec2(cell, cell),
+            )
+            .shrink(1.);
+            let intensity = if max > 0. { (minutes / max) as f32 } else { 0. };
+            painter.rect_filled(rect, 2., cold.lerp_to_gamma(hot, intensity));
+            if response.hover_pos().is_some_and(|p| rect.contains(p)) {
+                response.clone().on_hover_text(format!(
+                    "{} {:02}:00-{:02}:00: {:.0} active minutes",
+                    DAYS[day],
+                    hour,
+                    hour + 1,
+                    minutes
+                ));
+            }
+        }
+    }
+}
+
+// One row per application with a bar for each time it was in the foreground, idle
+// time is shaded across all the rows
+fn display_timeline_chart(ui: &mut Ui, intervals: &[Interval], timezone: Option<Tz>) {
+    let local = |t: DateTime<Utc>| match timezone {
+        Some(timezone) => t.with_timezone(&timezone).format("%H:%M:%S").to_string(),
+        None => t.with_timezone(&Local).format("%H:%M:%S").to_string(),
+    };
+    let mut apps: Vec<&str> = vec![];
+    for interval in intervals.iter().filter(|i| i.active) {
+        if !apps.contains(&interval.app_name.as_str()) {
+            apps.push(&interval.app_name);
+        }
+    }
+    let (Some(first), Some(last)) = (
+        intervals.iter().map(|i| i.start).min(),
+        intervals.iter().map(|i| i.end).max(),
+    ) else {
+        return;
+    };
+    let span = (last - first).num_milliseconds().max(1) as f32;
+
+    let label_width = 150.;
+    let row_height = 18.;
+    let axis_height = 16.;
+    let width = ui.available_width().max(label_width + 100.);
+    let (response, painter) = ui.allocate_painter(
+        egui::vec2(width, axis_height + row_height * apps.len().max(1) as f32),
+        egui::Sense::hover(),
+    );
+    let chart = egui::Rect::from_min_max(
+        response.rect.min + egui::vec2(label_width, axis_height),
+        response.rect.max,
+    );
+    let x = |t: DateTime<Utc>| {
+        chart.left() + chart.width() * (t - first).num_milliseconds() as f32 / span
+    };
+    let text_color = ui.visuals().text_color();
+    let font = egui::FontId::proportional(10.);
+    for i in 0..=4 {
+        let t = first + (last - first) * i / 4;
+        painter.text(
+            egui::pos2(x(t), response.rect.top()),
+            if i == 4 {
+                egui::Align2::RIGHT_TOP
+            } else {
+                egui::Align2::LEFT_TOP
+            },
+            local(t),
+            font.clone(),
+            text_color,
+        );
+    }
+    for (row, app) in apps.iter().enumerate() {
+        painter.text(
+            egui::pos2(
+                response.rect.left(),
+                chart.top() + (row as f32 + 0.5) * row_height,
+            ),
+            egui::Align2::LEFT_CENTER,
+            app,
+            font.clone(),
+            text_color,
+        );
+    }
+
+    let idle = ui.visuals().faint_bg_color;
+    let hover = response.hover_pos();
+    let mut tooltip = None;
+    for interval in intervals {
+        let rect = if interval.active {
+            let row = apps
+                .iter()
+                .position(|a| *a == interval.app_name)
+                .unwrap_or_default();
+            egui::Rect::from_min_max(
+                egui::pos2(
+                    x(interval.start),
+                    chart.top() + row as f32 * row_height + 2.,
+                ),
+                egui::pos2(
+                    x(interval.end).max(x(interval.start) + 1.),
+                    chart.top() + (row as f32 + 1.) * row_height - 2.,
+                ),
+            )
+        } else {
+            egui::Rect::from_min_max(
+                egui::pos2(x(interval.start), chart.top()),
+                egui::pos2(x(interval.end), chart.bottom()),
+            )
+        };
+        let color = if interval.active {
+            app_color(&interval.app_name)
+        } else {
+            idle
+        };
+        painter.rect_filled(rect, 1., color);
+        if hover.is_some_and(|p| rect.contains(p)) && (interval.active || tooltip.is_none()) {
+            tooltip = Some(format!(
+                "{}\n{}\n{} - {} ({})",
+                if interval.active {
+                    &interval.app_name
+                } else {
+                    "Idle"
+                },
+                if interval.active && interval.title.is_empty() {
+                    "<no title>"
+                } else {
+                    &interval.title
+                },
+                local(interval.start),
+                local(interval.end),
+                humantime::Duration::from(Duration::from_secs(interval.duration().as_secs())),
+            ));
+        }
+    }
+    if let Some(tooltip) = tooltip {
+        response.on_hover_text(tooltip);
+    }
+}
+
+// Stable across frames and days so an application keeps its color
+fn app_color(app_name: &str) -> egui::Color32 {
+    let hash = app_name
+        .bytes()
+        .fold(0u32, |h, b| h.wrapping_mul(31).wrapping_add(b as u32));
+    egui::ecolor::Hsva::new((hash % 360) as f32 / 360., 0.6, 0.8, 1.).into()
+}
+
+fn format_statistic(statistic: Statistic, value: f64) -> String {
+    match statistic {
+        Statistic::DaysUsed => format!("{}", value),
+        Statistic::Share => format!("{:.1}%", value),
+        _ => humantime::Duration::from(Duration::from_secs(value.round() as u64)).to_string(),
+    }
+}
+
+fn display_statistics_days_selector(
+    ui: &mut Ui,
+    selection: &mut DaySelection,
+    mode: &mut AverageMode,
+    id: &str,
+) {
+    ui.horizontal(|ui| {
+        ui.label("Statistics over");
+        egui::ComboBox::from_id_salt(format!("{}_selection", id))
+            .selected_text(selection.label())
+            .show_ui(ui, |ui| {
+                for s in DaySelection::ALL {
+                    ui.selectable_value(selection, s, s.label());
+                }
+            });
+        egui::ComboBox::from_id_salt(format!("{}_mode", id))
+            .selected_text(mode.label())
+            .show_ui(ui, |ui| {
+                for m in AverageMode::ALL {
+                    ui.selectable_value(mode, m, m.label());
+                }
+            });
+    });
+}
+
+fn display_calendar_editor(ui: &mut Ui, calendar: &mut Calendar, new_holiday: &mut NaiveDate) {
+    ui.horizontal(|ui| {
+        ui.label("Work days");
+        for weekday in (0..7).filter_map(|i| Weekday::try_from(i).ok()) {
+            let mut checked = calendar.work_days.contains(&weekday);
+            if ui.checkbox(&mut checked, weekday.to_string()).changed() {
+                if checked {
+                    calendar.work_days.push(weekday);
+                    calendar.work_days.sort_by_key(|w| w.num_days_from_monday());
+                } else {
+                    calendar.work_days.retain(|w| *w != weekday);
+                }
+            }
+        }
+    });
+    ui.horizontal(|ui| {
+        ui.label("Holidays");
+        ui.add(DatePickerButton::new(new_holiday).id_salt("new_holiday"));
+        if ui.button("Add").clicked() {
+            calendar.holidays.insert(*new_holiday);
+        }
+    });
+    let mut removed = None;
+    egui::ScrollArea::vertical()
+        .id_salt("holidays")
+        .max_height(100.)
+        .show(ui, |ui| {
+            for holiday in &calendar.holidays {
+                ui.horizontal(|ui| {
+                    ui.label(holiday.format("%Y-%m-%d %a").to_string());
+                    if ui.small_button("Remove").clicked() {
+                        removed = Some(*holiday);
+                    }
+                });
+            }
+        });
+    if let Some(removed) = removed {
+        calendar.holidays.remove(&removed);
+    }
+}
+
+fn display_deltas(
+    ui: &mut Ui,
+    id: &str,
+    deltas: &[Delta],
+    before: &RangeInclusive<NaiveDate>,
+    after: &RangeInclusive<NaiveDate>,
+    format_period: impl Fn(&RangeInclusive<NaiveDate>) -> String,
+) {
+    if deltas.is_empty() {
+        ui.label("Nothing recorded in these periods");
+        return;
+    }
+    let format_duration =
+        |d: Duration| humantime::Duration::from(Duration::from_secs(d.as_secs())).to_string();
+    egui::Grid::new(id).striped(true).show(ui, |ui| {
+        ui.strong("");
+        ui.strong(format_period(before));
+        ui.strong(format_period(after));
+        ui.strong("Change");
+        ui.strong("%");
+        ui.end_row();
+        for delta in deltas {
+            ui.label(&delta.name);
+            ui.label(format_duration(delta.before));
+            ui.label(format_duration(delta.after));
+            let change = delta.change();
+            let color = if change > 0. {
+                ui.visuals().warn_fg_color
+            } else {
+                ui.visuals().text_color()
+            };
+            ui.colored_label(
+                color,
+                format!(
+                    "{}{}",
+                    if change < 0. { "-" } else { "+" },
+                    format_duration(Duration::from_secs_f64(change.abs()))
+                ),
+            );
+            ui.label(
+                delta
+                    .change_percentage()
+                    .map_or("new".to_string(), |p| format!("{:+.0}%", p)),
+            );
+            ui.end_row();
+        }
+    });
+}
@@ -0,0 +1,395 @@
+use std::collections::BTreeSet;
+
+use chrono::{Datelike, Days, Local, NaiveDate, NaiveDateTime, TimeZone, Weekday};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct Calendar {
+    #[serde(default = "default_work_days")]
+    pub work_days: Vec<Weekday>,
+    #[serde(default)]
+    pub holidays: BTreeSet<NaiveDate>,
+}
+
+impl Default for Calendar {
+    fn default() -> Self {
+        Self {
+            work_days: default_work_days(),
+            holidays: BTreeSet::new(),
+        }
+    }
+}
+
+fn default_work_days() -> Vec<Weekday> {
+    vec![
+        Weekday::Mon,
+        Weekday::Tue,
+        Weekday::Wed,
+        Weekday::Thu,
+        Weekday::Fri,
+    ]
+}
+
+impl Calendar {
+    pub fn is_work_day(&self, day: NaiveDate) -> bool {
+        self.work_days.contains(&day.weekday()) && !self.holidays.contains(&day)
+    }
+
+    /// Adds the days of every event in the iCalendar file as holidays, the times are
+    /// moved to the timezone of the user first
+    pub fn import_ics(
+        &mut self,
+        content: &str,
+        timezone: Option<Tz>,
+        today: NaiveDate,
+    ) -> Result<IcsImport, String> {
+        let (days, ignored_rules) = parse_ics_days(content, timezone, today)?;
+        let before = self.holidays.len();
+        self.holidays.extend(days);
+        Ok(IcsImport {
+            added: self.holidays.len() - before,
+            ignored_rules,
+        })
+    }
+}
+
+/// Which days the statistics are calculated over
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DaySelection {
+    #[default]
+    AllDays,
+    WorkDays,
+    DaysOff,
+}
+
+impl DaySelection {
+    pub const ALL: [DaySelection; 3] = [
+        DaySelection::AllDays,
+        DaySelection::WorkDays,
+        DaySelection::DaysOff,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            DaySelection::AllDays => "All days",
+            DaySelection::WorkDays => "Work days",
+            DaySelection::DaysOff => "Weekends and holidays",
+        }
+    }
+
+    pub fn includes(&self, day: NaiveDate, calendar: &Calendar) -> bool {
+        match self {
+            DaySelection::AllDays => true,
+            DaySelection::WorkDays => calendar.is_work_day(day),
+            DaySelection::DaysOff => !calendar.is_work_day(day),
+        }
+    }
+}
+
+/// Outcome of an iCalendar import
+#[derive(Debug, PartialEq)]
+pub struct IcsImport {
+    /// Days that were not holidays already
+    pub added: usize,
+    /// Repeating events with a rule that isn't supported, only their first day is added
+    pub ignored_rules: usize,
+}
+
+// Start or end of an event: a date for all day events, otherwise the time on the wall
+// clock of the user
+#[derive(Clone, Copy)]
+enum IcsTime {
+    Date(NaiveDate),
+    DateTime(NaiveDateTime),
+}
+
+impl IcsTime {
+    fn date(&self) -> NaiveDate {
+        match self {
+            IcsTime::Date(date) => *date,
+            IcsTime::DateTime(time) => time.date(),
+        }
+    }
+}
+
+#[derive(Default)]
+struct IcsEvent {
+    start: Option<IcsTime>,
+    end: Option<IcsTime>,
+    rule: Option<String>,
+}
+
+// Only the dates of the events matter: the start day, up to the day before the end for
+// the events lasting more than a day as the end of all day events is exclusive. The
+// repeating events without an end are expanded up to the end of the next year.
+fn parse_ics_days(
+    content: &str,
+    timezone: Option<Tz>,
+    today: NaiveDate,
+) -> Result<(Vec<NaiveDate>, usize), String> {
+    // Long lines are folded with a line break followed by a space or a tab
+    let unfolded = content
+        .replace("\r\n", "\n")
+        .replace("\n ", "")
+        .replace("\n\t", "");
+    if !unfolded.trim_start().starts_with("BEGIN:VCALENDAR") {
+        return Err("not an iCalendar file".to_string());
+    }
+    let horizon = NaiveDate::from_ymd_opt(today.year() + 1, 12, 31).unwrap_or(NaiveDate::MAX);
+    let mut days = vec![];
+    let mut ignored_rules = 0;
+    let mut event: Option<IcsEvent> = None;
+    for line in unfolded.lines() {
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        // Parameters like `;VALUE=DATE` or `;TZID=...` follow the property name
+        let mut parameters = name.split(';');
+        let name = parameters.next().unwrap_or(name);
+        let tzid = parameters.find_map(|p| p.strip_prefix("TZID="));
+        match (name, event.as_mut()) {
+            ("BEGIN", None) if value == "VEVENT" => event = Some(IcsEvent::default()),
+            ("DTSTART", Some(event)) => event.start = Some(parse_ics_time(value, tzid, timezone)?),
+            ("DTEND", Some(event)) => event.end = Some(parse_ics_time(value, tzid, timezone)?),
+            ("RRULE", Some(event)) => event.rule = Some(value.to_string()),
+            ("END", Some(IcsEvent { start, end, rule })) if value == "VEVENT" => {
+                if let Some(start) = start {
+                    let first = start.date();
+                    let last = match end {
+                        Some(IcsTime::Date(end)) => end.pred_opt(),
+                        // Ending at midnight doesn't take any of the next day
+                        Some(IcsTime::DateTime(end)) => {
+                            Some((*end - chrono::Duration::seconds(1)).date())
+                        }
+                        None => None,
+                    }
+                    .filter(|last| *last > first)
+                    .unwrap_or(first);
+                    let starts = match rule {
+                        Some(rule) => recurrences(first, rule, horizon).unwrap_or_else(|| {
+                            ignored_rules += 1;
+                            vec![first]
+                        }),
+                        None => vec![first],
+                    };
+                    let length = Days::new((last - first).num_days() as u64);
+                    for start in starts {
+                        let last = start.checked_add_days(length).unwrap_or(start);
+                        days.extend(start.iter_days().take_while(|day| *day <= last));
+                    }
+                }
+                event = None;
+            }
+            _ => {}
+        }
+    }
+    Ok((days, ignored_rules))
+}
+
+// UTC times and the ones of a known timezone are moved to the user timezone, the
+// floating ones are already on the wall clock
+fn parse_ics_time(
+    value: &str,
+    tzid: Option<&str>,
+    timezone: Option<Tz>,
+) -> Result<IcsTime, String> {
+    let invalid = || format!("invalid date {}", value);
+    if value.len() == 8 {
+        return parse_ics_date(value).map(IcsTime::Date);
+    }
+    let time = NaiveDateTime::parse_from_str(value.trim_end_matches('Z'), "%Y%m%dT%H%M%S")
+        .map_err(|_| invalid())?;
+    let utc = if value.ends_with('Z') {
+        Some(time.and_utc())
+    } else {
+        tzid.and_then(|tzid| tzid.parse::<Tz>().ok())
+            .and_then(|tzid| tzid.from_local_datetime(&time).earliest())
+            .map(|time| time.to_utc())
+    };
+    Ok(IcsTime::DateTime(match (utc, timezone) {
+        (Some(utc), Some(timezone)) => utc.with_timezone(&timezone).naive_local(),
+        (Some(utc), None) => utc.with_timezone(&Local).naive_local(),
+        (None, _) => time,
+    }))
+}
+
+fn parse_ics_date(value: &str) -> Result<NaiveDate, String> {
+    value
+        .get(..8)
+        .and_then(|date| NaiveDate::parse_from_str(date, "%Y%m%d").ok())
+        .ok_or_else(|| format!("invalid date {}", value))
+}
+
+// First day of each occurrence of a repeating event, for the daily, weekly, monthly and
+// yearly rules with an interval and a count or an end. None for the rules with other
+// parts, like BYDAY.
+fn recurrences(start: NaiveDate, rule: &str, horizon: NaiveDate) -> Option<Vec<NaiveDate>> {
+    let mut frequency = None;
+    let mut interval = 1;
+    let mut count = None;
+    let mut until = horizon;
+    for part in rule.split(';') {
+        let (name, value) = part.split_once('=')?;
+        match name {
+            "FREQ" => frequency = Some(value),
+            "INTERVAL" => interval = value.parse::<u32>().ok().filter(|i| *i > 0)?,
+            "COUNT" => count = Some(value.parse::<usize>().ok()?),
+            "UNTIL" => until = parse_ics_date(value).ok()?,
+            // Only changes which weeks BYDAY picks
+            "WKST" => {}
+            _ => return None,
+        }
+    }
+    let frequency = frequency?;
+    if !["DAILY", "WEEKLY", "MONTHLY", "YEARLY"].contains(&frequency) {
+        return None;
+    }
+    let mut days = vec![];
+    for n in 0u32.. {
+        let Some(step) = n.checked_mul(interval) else {
+            break;
+        };
+        // Months without the day, like the 31st or February 29th, are skipped
+        let (period, day) = match frequency {
+            "DAILY" | "WEEKLY" => {
+                let days_per_step = if frequency == "WEEKLY" { 7 } else { 1 };
+                let day = start.checked_add_days(Days::new(step as u64 * days_per_step));
+                (day, day)
+            }
+            _ => {
+                let months_per_step = if frequency == "MONTHLY" { 1 } else { 12 };
+                let month = start.month0() as i64 + step as i64 * months_per_step;
+                let year = i32::try_from(start.year() as i64 + month / 12).ok();
+                let month = (month % 12) as u32 + 1;
+                (
+                    year.and_then(|year| NaiveDate::from_ymd_opt(year, month, 1)),
+                    year.and_then(|year| NaiveDate::from_ymd_opt(year, month, start.day())),
+                )
+            }
+        };
+        if period.is_none_or(|period| period > until)
+            || count.is_some_and(|count| days.len() >= count)
+        {
+            break;
+        }
+        days.extend(day.filter(|day| *day <= until));
+    }
+    Some(days)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::date;
+
+    #[test]
+    fn should_exclude_weekends_and_holidays_from_work_days() {
+        let mut calendar = Calendar::default();
+        calendar.holidays.insert(date(12, 25));
+        assert!(calendar.is_work_day(date(10, 16)));
+        assert!(!calendar.is_work_day(date(10, 17)));
+        assert!(!calendar.is_work_day(date(12, 25)));
+        assert!(DaySelection::DaysOff.includes(date(12, 25), &calendar));
+        assert!(DaySelection::AllDays.includes(date(10, 17), &calendar));
+    }
+
+    #[test]
+    fn should_import_holidays_from_ics() {
+        let ics = "BEGIN:VCALENDAR\r\n\
+                   VERSION:2.0\r\n\
+                   BEGIN:VEVENT\r\n\
+                   DTSTART;VALUE=DATE:20261225\r\n\
+                   DTEND;VALUE=DATE:20261227\r\n\
+                   SUMMARY:Christmas and\r\n  St. Stephen\r\n\
+                   END:VEVENT\r\n\
+                   BEGIN:VEVENT\r\n\
+                   DTSTART:20261101T090000Z\r\n\
+                   SUMMARY:All Saints\r\n\
+                   END:VEVENT\r\n\
+                   END:VCALENDAR\r\n";
+        let mut calendar = Calendar::default();
+        calendar.holidays.insert(date(11, 1));
+        assert_eq!(
+            Ok(IcsImport {
+                added: 2,
+                ignored_rules: 0
+            }),
+            calendar.import_ics(ics, Some(Tz::UTC), date(10, 16))
+        );
+        assert_eq!(
+            vec![date(11, 1), date(12, 25), date(12, 26)],
+            calendar.holidays.into_iter().collect::<Vec<_>>()
+        );
+        assert!(Calendar::default()
+            .import_ics("not a calendar", None, date(10, 16))
+            .is_err());
+    }
+
+    #[test]
+    fn should_take_the_day_in_the_user_timezone() {
+        let ics = "BEGIN:VCALENDAR\r\n\
+                   BEGIN:VEVENT\r\n\
+                   DTSTART:20261101T230000Z\r\n\
+                   DTEND:20261102T010000Z\r\n\
+                   END:VEVENT\r\n\
+                   BEGIN:VEVENT\r\n\
+                   DTSTART;TZID=America/New_York:20261105T200000\r\n\
+                   END:VEVENT\r\n\
+                   END:VCALENDAR\r\n";
+        let mut calendar = Calendar::default();
+        calendar
+            .import_ics(ics, Some(Tz::Europe__Rome), date(10, 16))
+            .unwrap();
+        assert_eq!(
+            vec![date(11, 2), date(11, 6)],
+            calendar.holidays.into_iter().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn should_expand_repeating_events() {
+        let ics = "BEGIN:VCALENDAR\r\n\
+                   BEGIN:VEVENT\r\n\
+                   DTSTART;VALUE=DATE:20261102\r\n\
+                   RRULE:FREQ=WEEKLY;INTERVAL=2;COUNT=3\r\n\
+                   END:VEVENT\r\n\
+                   BEGIN:VEVENT\r\n\
+                   DTSTART;VALUE=DATE:20261228\r\n\
+                   DTEND;VALUE=DATE:20261230\r\n\
+                   RRULE:FREQ=DAILY;INTERVAL=3;UNTIL=20270103\r\n\
+                   END:VEVENT\r\n\
+                   BEGIN:VEVENT\r\n\
+                   DTSTART;VALUE=DATE:20241225\r\n\
+                   RRULE:FREQ=YEARLY\r\n\
+                   END:VEVENT\r\n\
+                   BEGIN:VEVENT\r\n\
+                   DTSTART;VALUE=DATE:20261001\r\n\
+                   RRULE:FREQ=MONTHLY;BYDAY=1MO\r\n\
+                   END:VEVENT\r\n\
+                   END:VCALENDAR\r\n";
+        let mut calendar = Calendar::default();
+        let imported = calendar.import_ics(ics, None, date(10, 16)).unwrap();
+        assert_eq!(1, imported.ignored_rules);
+        let day = |year, month, day| NaiveDate::from_ymd_opt(year, month, day).unwrap();
+        assert_eq!(
+            vec![
+                day(2024, 12, 25),
+                day(2025, 12, 25),
+                date(10, 1),
+                date(11, 2),
+                date(11, 16),
+                date(11, 30),
+                date(12, 25),
+                date(12, 28),
+                date(12, 29),
+                date(12, 31),
+                day(2027, 1, 1),
+                day(2027, 1, 3),
+                day(2027, 1, 4),
+                day(2027, 12, 25),
+            ],
+            calendar.holidays.into_iter().collect::<Vec<_>>()
+        );
+    }
+}
@@ -0,0 +1,146 @@
+use std::{collections::BTreeMap, ops::RangeInclusive, time::Duration};
+
+use chrono::{Datelike, Days, Months, NaiveDate};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Comparison {
+    #[default]
+    ThisWeek,
+    LastWeek,
+    ThisMonth,
+    LastMonth,
+}
+
+impl Comparison {
+    pub const ALL: [Comparison; 4] = [
+        Comparison::ThisWeek,
+        Comparison::LastWeek,
+        Comparison::ThisMonth,
+        Comparison::LastMonth,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Comparison::ThisWeek => "This week vs last week",
+            Comparison::LastWeek => "Last week vs the week before",
+            Comparison::ThisMonth => "This month vs last month",
+            Comparison::LastMonth => "Last month vs the month before",
+        }
+    }
+
+    /// Previous and current period, weeks start on Monday. The current week and month
+    /// end today while the previous ones are complete.
+    pub fn periods(
+        &self,
+        today: NaiveDate,
+    ) -> (RangeInclusive<NaiveDate>, RangeInclusive<NaiveDate>) {
+        let week_start = today - Days::new(today.weekday().num_days_from_monday() as u64);
+        let month_start = today.with_day(1).unwrap_or(today);
+        let week = |start: NaiveDate| start..=start + Days::new(6);
+        let month = |start: NaiveDate| start..=start + Months::new(1) - Days::new(1);
+        match self {
+            Comparison::ThisWeek => (week(week_start - Days::new(7)), week_start..=today),
+            Comparison::LastWeek => (
+                week(week_start - Days::new(14)),
+                week(week_start - Days::new(7)),
+            ),
+            Comparison::ThisMonth => (month(month_start - Months::new(1)), month_start..=today),
+            Comparison::LastMonth => (
+                month(month_start - Months::new(2)),
+                month(month_start - Months::new(1)),
+            ),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Delta {
+    pub name: String,
+    pub before: Duration,
+    pub after: Duration,
+}
+
+impl Delta {
+    /// Change in seconds, negative when the time went down
+    pub fn change(&self) -> f64 {
+        self.after.as_secs_f64() - self.before.as_secs_f64()
+    }
+
+    /// Change relative to the previous period, missing when there was nothing to compare
+    pub fn change_percentage(&self) -> Option<f64> {
+        if self.before.is_zero() {
+            None
+        } else {
+            Some(self.change() * 100. / self.before.as_secs_f64())
+        }
+    }
+}
+
+/// Every name in either period sorted by the size of the change, biggest first
+pub fn compare(
+    before: &BTreeMap<String, Duration>,
+    after: &BTreeMap<String, Duration>,
+) -> Vec<Delta> {
+    let mut deltas = before
+        .keys()
+        .chain(after.keys())
+        .collect::<std::collections::BTreeSet<_>>()
+        .into_iter()
+        .map(|name| Delta {
+            name: name.clone(),
+            before: before.get(name).copied().unwrap_or_default(),
+            after: after.get(name).copied().unwrap_or_default(),
+        })
+        .collect::<Vec<_>>();
+    deltas.sort_by(|a, b| b.change().abs().total_cmp(&a.change().abs()));
+    deltas
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::date;
+
+    #[test]
+    fn should_calculate_periods() {
+        // A Friday
+        let today = date(10, 16);
+        assert_eq!(
+            (date(10, 5)..=date(10, 11), date(10, 12)..=today),
+            Comparison::ThisWeek.periods(today)
+        );
+        assert_eq!(
+            (date(9, 28)..=date(10, 4), date(10, 5)..=date(10, 11)),
+            Comparison::LastWeek.periods(today)
+        );
+        assert_eq!(
+            (date(9, 1)..=date(9, 30), date(10, 1)..=today),
+            Comparison::ThisMonth.periods(today)
+        );
+        assert_eq!(
+            (date(8, 1)..=date(8, 31), date(9, 1)..=date(9, 30)),
+            Comparison::LastMonth.periods(today)
+        );
+    }
+
+    #[test]
+    fn should_compare_periods() {
+        let before = BTreeMap::from([
+            ("chat".to_string(), Duration::from_secs(100)),
+            ("editor".to_string(), Duration::from_secs(1000)),
+        ]);
+        let after = BTreeMap::from([
+            ("chat".to_string(), Duration::from_secs(150)),
+            ("game".to_string(), Duration::from_secs(500)),
+        ]);
+        let deltas = compare(&before, &after);
+        assert_eq!(
+            vec!["editor", "game", "chat"],
+            deltas.iter().map(|d| d.name.as_str()).collect::<Vec<_>>()
+        );
+        assert_eq!(-1000., deltas[0].change());
+        assert_eq!(Some(-100.), deltas[0].change_percentage());
+        assert_eq!(None, deltas[1].change_percentage());
+        assert_eq!(Some(50.), deltas[2].change_percentage());
+    }
+}
@@ -0,0 +1,102 @@
+use std::{
+    fs::{File, TryLockError},
+    path::Path,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use crate::{history::History, load_live_data, LiveData, OpenStorage};
+
+pub const LOCK_FILE_NAME: &str = "time_back.lock";
+
+/// Lock held by the process recording the activity for as long as the file is open, so
+/// a window opened next to the headless tracker only shows its data. It is released by
+/// the system when the process ends, even when it is killed.
+pub fn lock_tracker(dir: &Path) -> Result<Option<File>, String> {
+    let file = File::options()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(dir.join(LOCK_FILE_NAME))
+        .map_err(|e| e.to_string())?;
+    match file.try_lock() {
+        Ok(()) => Ok(Some(file)),
+        Err(TryLockError::WouldBlock) => Ok(None),
+        Err(TryLockError::Error(e)) => Err(e.to_string()),
+    }
+}
+
+/// Waits for SIGINT or SIGTERM (Ctrl-C or Ctrl-Break on Windows), the tracking thread
+/// then saves the data one last time and stops
+pub fn stop_on_signal(running: Arc<AtomicBool>) -> Result<(), String> {
+    ctrlc::set_handler(move || {
+        eprintln!("Stopping, saving the data");
+        running.store(false, Ordering::SeqCst);
+    })
+    .map_err(|e| e.to_string())
+}
+
+/// Reloads what the headless tracker saved every few seconds, instead of recording
+pub fn spawn_viewer_thread(
+    live: LiveData,
+    history: Arc<Mutex<History>>,
+    config: Arc<Mutex<crate::Config>>,
+) {
+    std::thread::spawn(move || view(live, history, config, OpenStorage::new(false)));
+}
+
+/// Keeps the live data up to date with what the tracker saved, never returns. Also
+/// followed by the tracking thread when it can't lock a newly chosen output directory.
+pub fn view(
+    live: LiveData,
+    history: Arc<Mutex<History>>,
+    config: Arc<Mutex<crate::Config>>,
+    mut storage: OpenStorage,
+) {
+    loop {
+        std::thread::sleep(Duration::from_secs(5));
+        let config = config.lock().unwrap().clone();
+        let Some(storage) = storage.get(&config) else {
+            continue;
+        };
+        let today = config.current_day();
+        // Problems are reported by the tracker itself
+        let fresh = load_live_data(storage, today, &mut Vec::new());
+        let mut day = live.day.lock().unwrap();
+        if *day != today {
+            *history.lock().unwrap() = History::load(storage, today, &mut Vec::new());
+            *day = today;
+        }
+        replace(&live.window_time, &fresh.window_time);
+        replace(&live.window_titles, &fresh.window_titles);
+        replace(&live.input_stats, &fresh.input_stats);
+        *live.activity.lock().unwrap() = std::mem::take(&mut fresh.activity.lock().unwrap());
+    }
+}
+
+fn replace<V: Clone>(target: &dashmap::DashMap<String, V>, source: &dashmap::DashMap<String, V>) {
+    target.retain(|k, _| source.contains_key(k));
+    for entry in source.iter() {
+        target.insert(entry.key().clone(), entry.value().clone());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::test_dir;
+
+    #[test]
+    fn should_allow_a_single_tracker() {
+        let dir = test_dir("daemon-lock");
+        let lock = lock_tracker(&dir).unwrap();
+        assert!(lock.is_some());
+        assert!(lock_tracker(&dir).unwrap().is_none());
+        drop(lock);
+        assert!(lock_tracker(&dir).unwrap().is_some());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
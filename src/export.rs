@@ -0,0 +1,288 @@
+use std::{collections::BTreeMap, ops::RangeInclusive, time::Duration};
+
+use chrono::NaiveDate;
+use clap::ValueEnum;
+use serde::Serialize;
+
+use crate::{
+    history::{History, Today},
+    report::{report, GroupBy},
+};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum ExportFormat {
+    #[default]
+    Csv,
+    Json,
+    Markdown,
+}
+
+impl ExportFormat {
+    pub const ALL: [ExportFormat; 3] = [
+        ExportFormat::Csv,
+        ExportFormat::Json,
+        ExportFormat::Markdown,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "CSV",
+            ExportFormat::Json => "JSON",
+            ExportFormat::Markdown => "Markdown",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Json => "json",
+            ExportFormat::Markdown => "md",
+        }
+    }
+}
+
+/// Active time of an application in a category on a day
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct ExportRow {
+    pub date: NaiveDate,
+    pub app: String,
+    pub category: Option<String>,
+    pub seconds: u64,
+}
+
+/// One row for each day, application and category of the range. The time of the day
+/// totals missing from the interval log, recorded before it existed or imported as
+/// totals only, is in a row without a category.
+pub fn export_rows(
+    history: &History,
+    days: &RangeInclusive<NaiveDate>,
+    today: &Today,
+) -> Vec<ExportRow> {
+    let mut rows = vec![];
+    for day in history.tracked_days(days, today) {
+        let single_day = day..=day;
+        let mut totals: BTreeMap<(String, Option<String>), Duration> = BTreeMap::new();
+        let mut logged: BTreeMap<String, Duration> = BTreeMap::new();
+        for interval in history.intervals(&single_day, today).filter(|i| i.active) {
+            *totals
+                .entry((interval.app_name.clone(), interval.category.clone()))
+                .or_default() += interval.duration();
+            *logged.entry(interval.app_name.clone()).or_default() += interval.duration();
+        }
+        for (app, total) in history.app_totals(&single_day, today) {
+            let missing = total.saturating_sub(logged.get(&app).copied().unwrap_or_default());
+            if !missing.is_zero() {
+                *totals.entry((app, None)).or_default() += missing;
+            }
+        }
+        rows.extend(
+            totals
+                .into_iter()
+                .map(|((app, category), duration)| ExportRow {
+                    date: day,
+                    app,
+                    category,
+                    seconds: duration.as_secs(),
+                })
+                .filter(|row| row.seconds > 0),
+        );
+    }
+    rows
+}
+
+pub fn export(
+    history: &History,
+    days: &RangeInclusive<NaiveDate>,
+    today: &Today,
+    format: ExportFormat,
+) -> Result<String, String> {
+    let days = history.recorded_range(days, today);
+    match format {
+        ExportFormat::Csv => {
+            let mut writer = csv::Writer::from_writer(vec![]);
+            let rows = export_rows(history, &days, today);
+            // The header is only written with the first row otherwise
+            if rows.is_empty() {
+                writer
+                    .write_record(["date", "app", "category", "seconds"])
+                    .map_err(|e| e.to_string())?;
+            }
+            for row in rows {
+                writer.serialize(row).map_err(|e| e.to_string())?;
+            }
+            let bytes = writer.into_inner().map_err(|e| e.to_string())?;
+            String::from_utf8(bytes).map_err(|e| e.to_string())
+        }
+        ExportFormat::Json => serde_json::to_string_pretty(&serde_json::json!({
+            "from": days.start(),
+            "to": days.end(),
+            "entries": export_rows(history, &days, today),
+        }))
+        .map_err(|e| e.to_string()),
+        ExportFormat::Markdown => Ok(markdown(history, &days, today)),
+    }
+}
+
+fn markdown(history: &History, days: &RangeInclusive<NaiveDate>, today: &Today) -> String {
+    let duration = |seconds: u64| humantime::Duration::from(Duration::from_secs(seconds));
+    // Pipes would end the cell early
+    let escape = |name: &str| name.replace('|', "\\|");
+    let mut markdown = format!("# Time back! {} to {}\n", days.start(), days.end());
+    for (group_by, title) in [
+        (GroupBy::App, "Application"),
+        (GroupBy::Category, "Category"),
+    ] {
+        let rows = report(history, days, today, group_by);
+        if rows.is_empty() {
+            continue;
+        }
+        markdown += &format!(
+            "\n| {} | Total | Days | Avg/day | Share |\n| --- | ---: | ---: | ---: | ---: |\n",
+            title
+        );
+        for row in &rows {
+            markdown += &format!(
+                "| {} | {} | {} | {} | {:.1}% |\n",
+                escape(&row.name),
+                duration(row.seconds),
+                row.days,
+                duration(row.average_seconds),
+                row.share
+            );
+        }
+        markdown += &format!(
+            "| **Overall** | **{}** | | | |\n",
+            duration(rows.iter().map(|r| r.seconds).sum())
+        );
+    }
+    markdown
+}
+
+#[cfg(test)]
+mod tests {
+    use dashmap::DashMap;
+
+    use super::*;
+    use crate::{
+        storage::all_days,
+        test_utils::{date, interval},
+    };
+
+    #[test]
+    fn should_export_rows_by_day_app_and_category() {
+        let mut history = History::default();
+        // Recorded before the interval log
+        history.totals.insert(
+            date(10, 13),
+            BTreeMap::from([("editor".to_string(), Duration::from_secs(60))]),
+        );
+        history.totals.insert(
+            date(10, 14),
+            BTreeMap::from([("editor".to_string(), Duration::from_secs(900))]),
+        );
+        history.intervals.insert(
+            date(10, 14),
+            vec![
+                interval(
+                    "editor",
+                    Some("work"),
+                    "2026-10-14T08:00:00Z",
+                    "2026-10-14T08:10:00Z",
+                ),
+                interval(
+                    "editor",
+                    None,
+                    "2026-10-14T08:10:00Z",
+                    "2026-10-14T08:15:00Z",
+                ),
+            ],
+        );
+        let totals = DashMap::new();
+        let today = Today {
+            day: date(10, 15),
+            totals: &totals,
+            intervals: &[],
+        };
+        let csv = export(
+            &history,
+            &(date(10, 1)..=date(10, 15)),
+            &today,
+            ExportFormat::Csv,
+        )
+        .unwrap();
+        assert_eq!(
+            "date,app,category,seconds\n\
+             2026-10-13,editor,,60\n\
+             2026-10-14,editor,,300\n\
+             2026-10-14,editor,work,600\n",
+            csv
+        );
+        let markdown = export(&history, &all_days(), &today, ExportFormat::Markdown).unwrap();
+        assert!(markdown.starts_with("# Time back! 2026-10-13 to 2026-10-15\n"));
+        assert!(markdown.contains("| editor | 16m | 2 | 8m | 100.0% |\n"));
+        assert!(markdown.contains("| work | 10m | 1 | 10m | 100.0% |\n"));
+    }
+
+    #[test]
+    fn should_export_totals_missing_from_the_interval_log() {
+        let mut history = History::default();
+        // Imported as totals only into a day with an interval log
+        history.totals.insert(
+            date(10, 14),
+            BTreeMap::from([
+                ("editor".to_string(), Duration::from_secs(700)),
+                ("imported".to_string(), Duration::from_secs(3600)),
+            ]),
+        );
+        history.intervals.insert(
+            date(10, 14),
+            vec![interval(
+                "editor",
+                Some("work"),
+                "2026-10-14T08:00:00Z",
+                "2026-10-14T08:10:00Z",
+            )],
+        );
+        let totals = DashMap::new();
+        let today = Today {
+            day: date(10, 15),
+            totals: &totals,
+            intervals: &[],
+        };
+        assert_eq!(
+            "date,app,category,seconds\n\
+             2026-10-14,editor,,100\n\
+             2026-10-14,editor,work,600\n\
+             2026-10-14,imported,,3600\n",
+            export(&history, &all_days(), &today, ExportFormat::Csv).unwrap()
+        );
+    }
+
+    #[test]
+    fn should_export_an_empty_document_for_days_without_history() {
+        let mut history = History::default();
+        history.totals.insert(
+            date(10, 1),
+            BTreeMap::from([("editor".to_string(), Duration::from_secs(60))]),
+        );
+        let totals = DashMap::new();
+        let today = Today {
+            day: date(10, 16),
+            totals: &totals,
+            intervals: &[],
+        };
+        for days in [date(11, 1)..=date(11, 5), date(9, 1)..=date(9, 5)] {
+            assert_eq!(
+                "date,app,category,seconds\n",
+                export(&history, &days, &today, ExportFormat::Csv).unwrap()
+            );
+            let json: serde_json::Value =
+                serde_json::from_str(&export(&history, &days, &today, ExportFormat::Json).unwrap())
+                    .unwrap();
+            assert_eq!(days.start().to_string(), json["from"]);
+            assert_eq!(days.end().to_string(), json["to"]);
+            assert!(json["entries"].as_array().unwrap().is_empty());
+            assert!(export(&history, &days, &today, ExportFormat::Markdown).is_ok());
+        }
+    }
+}
@@ -0,0 +1,111 @@
+use chrono::{DateTime, Datelike, Local, NaiveDateTime, Timelike, Utc};
+use chrono_tz::Tz;
+
+use crate::activity::Interval;
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum HeatmapFilter {
+    #[default]
+    All,
+    App(String),
+    Category(String),
+}
+
+impl HeatmapFilter {
+    fn matches(&self, interval: &Interval) -> bool {
+        match self {
+            HeatmapFilter::All => true,
+            HeatmapFilter::App(app_name) => interval.app_name == *app_name,
+            HeatmapFilter::Category(category) => interval.category.as_ref() == Some(category),
+        }
+    }
+}
+
+/// Active minutes by day of the week (Monday first) and hour of the day
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Heatmap {
+    pub minutes: [[f64; 24]; 7],
+}
+
+impl Heatmap {
+    pub fn new<'a>(
+        intervals: impl Iterator<Item = &'a Interval>,
+        filter: &HeatmapFilter,
+        timezone: Option<Tz>,
+    ) -> Self {
+        let mut heatmap = Self::default();
+        let local = |t: DateTime<Utc>| match timezone {
+            Some(timezone) => t.with_timezone(&timezone).naive_local(),
+            None => t.with_timezone(&Local).naive_local(),
+        };
+        for interval in intervals.filter(|i| i.active && filter.matches(i)) {
+            // Split on the wall clock so the hours are the ones the user saw, the odd
+            // interval crossing a DST change is off by the change at most
+            let mut start = local(interval.start);
+            let end = local(interval.end);
+            while start < end {
+                let next_hour = start.date().and_hms_opt(start.hour(), 0, 0).unwrap()
+                    + chrono::Duration::hours(1);
+                let slice_end = next_hour.min(end);
+                heatmap.add(
+                    start,
+                    (slice_end - start).num_milliseconds() as f64 / 60_000.,
+                );
+                start = slice_end;
+            }
+        }
+        heatmap
+    }
+
+    fn add(&mut self, at: NaiveDateTime, minutes: f64) {
+        self.minutes[at.weekday().num_days_from_monday() as usize][at.hour() as usize] += minutes;
+    }
+
+    pub fn max(&self) -> f64 {
+        self.minutes.iter().flatten().copied().fold(0., f64::max)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::interval;
+
+    #[test]
+    fn should_split_intervals_across_hours() {
+        // A Friday
+        let intervals = [interval(
+            "editor",
+            None,
+            "2026-10-16T08:40:00Z",
+            "2026-10-16T10:10:00Z",
+        )];
+        let heatmap = Heatmap::new(intervals.iter(), &HeatmapFilter::All, Some(Tz::UTC));
+        assert_eq!(20., heatmap.minutes[4][8]);
+        assert_eq!(60., heatmap.minutes[4][9]);
+        assert_eq!(10., heatmap.minutes[4][10]);
+        assert_eq!(60., heatmap.max());
+    }
+
+    #[test]
+    fn should_use_timezone_and_filter() {
+        let intervals = [
+            interval(
+                "editor",
+                Some("work"),
+                "2026-10-16T22:30:00Z",
+                "2026-10-16T22:45:00Z",
+            ),
+            interval("game", None, "2026-10-16T22:45:00Z", "2026-10-16T23:00:00Z"),
+        ];
+        let rome: Tz = "Europe/Rome".parse().unwrap();
+        let heatmap = Heatmap::new(
+            intervals.iter(),
+            &HeatmapFilter::Category("work".to_string()),
+            Some(rome),
+        );
+        // Saturday 00:30 in Rome
+        assert_eq!(15., heatmap.minutes[5][0]);
+        assert_eq!(15., heatmap.minutes.iter().flatten().sum::<f64>());
+    }
+}
@@ -0,0 +1,648 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    ops::RangeInclusive,
+    time::Duration,
+};
+
+use chrono::{Datelike, Days, NaiveDate};
+use dashmap::DashMap;
+
+use crate::{
+    activity::Interval,
+    productivity::{ProductivityLabels, ProductivitySummary},
+    storage::{all_days, Storage},
+};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DateRange {
+    #[default]
+    All,
+    Last7Days,
+    Last30Days,
+    Last90Days,
+    ThisWeek,
+    ThisMonth,
+    Custom,
+}
+
+impl DateRange {
+    pub const ALL: [DateRange; 7] = [
+        DateRange::All,
+        DateRange::Last7Days,
+        DateRange::Last30Days,
+        DateRange::Last90Days,
+        DateRange::ThisWeek,
+        DateRange::ThisMonth,
+        DateRange::Custom,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            DateRange::All => "All time",
+            DateRange::Last7Days => "Last 7 days",
+            DateRange::Last30Days => "Last 30 days",
+            DateRange::Last90Days => "Last 90 days",
+            DateRange::ThisWeek => "This week",
+            DateRange::ThisMonth => "This month",
+            DateRange::Custom => "Custom",
+        }
+    }
+
+    /// Days covered by the range, today included. Weeks start on Monday.
+    pub fn days(
+        &self,
+        today: NaiveDate,
+        custom: &RangeInclusive<NaiveDate>,
+    ) -> RangeInclusive<NaiveDate> {
+        let last_days = |n: u64| today.checked_sub_days(Days::new(n - 1)).unwrap_or(today)..=today;
+        match self {
+            DateRange::All => all_days(),
+            DateRange::Last7Days => last_days(7),
+            DateRange::Last30Days => last_days(30),
+            DateRange::Last90Days => last_days(90),
+            DateRange::ThisWeek => last_days(today.weekday().number_from_monday() as u64),
+            DateRange::ThisMonth => last_days(today.day() as u64),
+            DateRange::Custom => custom.clone(),
+        }
+    }
+}
+
+/// Days the averages and the other statistics are calculated over
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum AverageMode {
+    /// Only the days the application was used
+    #[default]
+    DaysUsed,
+    /// Every selected day of the range, the ones without usage count as zero
+    EveryDay,
+}
+
+impl AverageMode {
+    pub const ALL: [AverageMode; 2] = [AverageMode::DaysUsed, AverageMode::EveryDay];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            AverageMode::DaysUsed => "Days used",
+            AverageMode::EveryDay => "Every day",
+        }
+    }
+}
+
+// The day being tracked, taken from the live data as it changes every tick
+pub struct Today<'a> {
+    pub day: NaiveDate,
+    pub totals: &'a DashMap<String, Duration>,
+    pub intervals: &'a [Interval],
+}
+
+// `BTreeMap::range` panics on a range ending before it starts, which a range in the future
+// limited to today does
+fn in_range<'a, V>(
+    map: &'a BTreeMap<NaiveDate, V>,
+    days: &RangeInclusive<NaiveDate>,
+) -> impl Iterator<Item = (&'a NaiveDate, &'a V)> {
+    (!days.is_empty())
+        .then(|| map.range(days.clone()))
+        .into_iter()
+        .flatten()
+}
+
+// Past days kept in memory so the aggregates over any range don't need to read the
+// storage again. The interval logs are only read for the ranges that need them.
+#[derive(Default)]
+pub struct History {
+    pub totals: BTreeMap<NaiveDate, BTreeMap<String, Duration>>,
+    pub intervals: BTreeMap<NaiveDate, Vec<Interval>>,
+    intervals_read: Option<RangeInclusive<NaiveDate>>,
+}
+
+impl History {
+    /// The totals of everything recorded except the day being tracked, that is in the live
+    /// data. The interval logs are read by `load_intervals` when a range needs them.
+    pub fn load(storage: &dyn Storage, current_day: NaiveDate, warnings: &mut Vec<String>) -> Self {
+        Self::load_range(storage, all_days(), current_day, false, warnings)
+    }
+
+    /// Only the days of the range, without the interval log when it isn't needed
+    pub fn load_range(
+        storage: &dyn Storage,
+        days: RangeInclusive<NaiveDate>,
+        current_day: NaiveDate,
+        with_intervals: bool,
+        warnings: &mut Vec<String>,
+    ) -> Self {
+        let mut totals = storage.load_totals(days.clone(), warnings);
+        let mut intervals = if with_intervals {
+            storage.load_intervals(days.clone(), warnings)
+        } else {
+            BTreeMap::new()
+        };
+        totals.remove(&current_day);
+        intervals.remove(&current_day);
+        Self {
+            totals,
+            intervals,
+            intervals_read: with_intervals.then_some(days),
+        }
+    }
+
+    pub fn has_intervals(&self, days: &RangeInclusive<NaiveDate>) -> bool {
+        days.is_empty()
+            || self
+                .intervals_read
+                .as_ref()
+                .is_some_and(|read| read.contains(days.start()) && read.contains(days.end()))
+    }
+
+    /// Reads the interval logs of the days in the range not read yet
+    pub fn load_intervals(
+        &mut self,
+        storage: &dyn Storage,
+        days: &RangeInclusive<NaiveDate>,
+        current_day: NaiveDate,
+        warnings: &mut Vec<String>,
+    ) {
+        if self.has_intervals(days) {
+            return;
+        }
+        // The days read stay a single range, only the ones around it are added
+        let (missing, read) = match self.intervals_read.take() {
+            Some(read) => {
+                let start = *days.start().min(read.start());
+                let end = *days.end().max(read.end());
+                let before = read.start().pred_opt().map(|last| start..=last);
+                let after = read.end().succ_opt().map(|first| first..=end);
+                (before.into_iter().chain(after).collect(), start..=end)
+            }
+            None => (vec![days.clone()], days.clone()),
+        };
+        for days in missing.into_iter().filter(|days| !days.is_empty()) {
+            self.intervals
+                .extend(storage.load_intervals(days, warnings));
+        }
+        self.intervals.remove(&current_day);
+        self.intervals_read = Some(read);
+    }
+
+    /// Time of each application on the selected days of the range counted by the mode
+    pub fn durations(
+        &self,
+        days: &RangeInclusive<NaiveDate>,
+        today: &Today,
+        mode: AverageMode,
+        selected: impl Fn(NaiveDate) -> bool,
+    ) -> BTreeMap<String, Vec<Duration>> {
+        let mut values: BTreeMap<String, Vec<Duration>> = BTreeMap::new();
+        if mode == AverageMode::DaysUsed {
+            for (_, totals) in in_range(&self.totals, days).filter(|(d, _)| selected(**d)) {
+                for (k, v) in totals {
+                    values.entry(k.clone()).or_default().push(*v);
+                }
+            }
+            if days.contains(&today.day) && selected(today.day) {
+                for v in today.totals.iter() {
+                    values.entry(v.key().clone()).or_default().push(*v.value());
+                }
+            }
+            return values;
+        }
+        let counted = self
+            .recorded_span(days, today)
+            .filter(|day| selected(*day))
+            .collect::<Vec<_>>();
+        for (i, day) in counted.iter().enumerate() {
+            let totals = self.day_totals(*day, today);
+            for (k, v) in &totals {
+                // Zeros for the days before the first use, the next ones are filled below
+                values
+                    .entry(k.clone())
+                    .or_insert_with(|| vec![Duration::ZERO; i])
+                    .push(*v);
+            }
+            for (k, v) in values.iter_mut() {
+                if !totals.contains_key(k) {
+                    v.push(Duration::ZERO);
+                }
+            }
+        }
+        values
+    }
+
+    /// Time of the application on each day of the range, starting from the first recorded
+    /// day. Days it wasn't used are zero so the gaps show in the trend.
+    pub fn daily_durations(
+        &self,
+        days: &RangeInclusive<NaiveDate>,
+        app_name: &str,
+        today: &Today,
+    ) -> Vec<(NaiveDate, Duration)> {
+        self.recorded_span(days, today)
+            .map(|day| {
+                let duration = if day == today.day {
+                    today.totals.get(app_name).map(|d| *d)
+                } else {
+                    self.totals.get(&day).and_then(|t| t.get(app_name)).copied()
+                };
+                (day, duration.unwrap_or_default())
+            })
+            .collect()
+    }
+
+    /// Days of the range with something recorded, today included
+    pub fn tracked_days(&self, days: &RangeInclusive<NaiveDate>, today: &Today) -> Vec<NaiveDate> {
+        in_range(&self.totals, days)
+            .map(|(day, _)| *day)
+            .chain((days.contains(&today.day) && !today.totals.is_empty()).then_some(today.day))
+            .collect()
+    }
+
+    /// The range limited to the days from the first recorded one up to today, or the
+    /// range itself when none of it was recorded, e.g. in the future
+    pub fn recorded_range(
+        &self,
+        days: &RangeInclusive<NaiveDate>,
+        today: &Today,
+    ) -> RangeInclusive<NaiveDate> {
+        let recorded = self.limit_to_recorded(days, today);
+        if recorded.is_empty() {
+            days.clone()
+        } else {
+            recorded
+        }
+    }
+
+    // Ends before it starts when none of the range was recorded
+    fn limit_to_recorded(
+        &self,
+        days: &RangeInclusive<NaiveDate>,
+        today: &Today,
+    ) -> RangeInclusive<NaiveDate> {
+        let first_recorded = self.totals.keys().next().copied().unwrap_or(today.day);
+        (*days.start()).max(first_recorded)..=(*days.end()).min(today.day)
+    }
+
+    // Nothing was tracked before the first recorded day so the days before can't count
+    // as days without usage
+    fn recorded_span(
+        &self,
+        days: &RangeInclusive<NaiveDate>,
+        today: &Today,
+    ) -> impl Iterator<Item = NaiveDate> {
+        let span = self.limit_to_recorded(days, today);
+        let end = *span.end();
+        span.start().iter_days().take_while(move |day| *day <= end)
+    }
+
+    fn day_totals(&self, day: NaiveDate, today: &Today) -> BTreeMap<String, Duration> {
+        if day == today.day {
+            today
+                .totals
+                .iter()
+                .map(|v| (v.key().clone(), *v.value()))
+                .collect()
+        } else {
+            self.totals.get(&day).cloned().unwrap_or_default()
+        }
+    }
+
+    /// Total time of each application over the range
+    pub fn app_totals(
+        &self,
+        days: &RangeInclusive<NaiveDate>,
+        today: &Today,
+    ) -> BTreeMap<String, Duration> {
+        self.durations(days, today, AverageMode::DaysUsed, |_| true)
+            .into_iter()
+            .map(|(k, v)| (k, v.iter().sum()))
+            .collect()
+    }
+
+    /// Total active time of each category over the range, days recorded before the
+    /// interval log have no categories
+    pub fn category_totals(
+        &self,
+        days: &RangeInclusive<NaiveDate>,
+        today: &Today,
+    ) -> BTreeMap<String, Duration> {
+        let mut totals: BTreeMap<String, Duration> = BTreeMap::new();
+        for interval in self.intervals(days, today).filter(|i| i.active) {
+            if let Some(category) = &interval.category {
+                *totals.entry(category.clone()).or_default() += interval.duration();
+            }
+        }
+        totals
+    }
+
+    /// Interval logs of the days in the range
+    pub fn intervals<'a>(
+        &'a self,
+        days: &RangeInclusive<NaiveDate>,
+        today: &'a Today,
+    ) -> impl Iterator<Item = &'a Interval> {
+        let today_intervals = if days.contains(&today.day) {
+            today.intervals
+        } else {
+            &[]
+        };
+        in_range(&self.intervals, days)
+            .flat_map(|(_, intervals)| intervals)
+            .chain(today_intervals)
+    }
+
+    pub fn app_names(&self, today: &Today) -> BTreeSet<String> {
+        self.totals
+            .values()
+            .flat_map(|totals| totals.keys().cloned())
+            .chain(today.totals.iter().map(|v| v.key().clone()))
+            .collect()
+    }
+
+    pub fn productivity_scores(
+        &self,
+        days: &RangeInclusive<NaiveDate>,
+        labels: &ProductivityLabels,
+        today: &Today,
+    ) -> Vec<(NaiveDate, f64)> {
+        let mut scores = in_range(&self.totals, days)
+            .map(|(day, _)| day)
+            .chain(in_range(&self.intervals, days).map(|(day, _)| day))
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .filter_map(|day| {
+                let intervals = self.intervals.get(day).map_or(&[][..], |i| i.as_slice());
+                let totals = self.totals.get(day).into_iter().flatten();
+                day_summary(labels, intervals, totals.map(|(k, v)| (k.as_str(), *v)))
+                    .score()
+                    .map(|score| (*day, score))
+            })
+            .collect::<Vec<_>>();
+        if days.contains(&today.day) {
+            let totals = today
+                .totals
+                .iter()
+                .map(|v| (v.key().clone(), *v.value()))
+                .collect::<Vec<_>>();
+            let summary = day_summary(
+                labels,
+                today.intervals,
+                totals.iter().map(|(k, v)| (k.as_str(), *v)),
+            );
+            scores.extend(summary.score().map(|score| (today.day, score)));
+        }
+        scores
+    }
+}
+
+fn day_summary<'a>(
+    labels: &ProductivityLabels,
+    intervals: &'a [Interval],
+    totals: impl Iterator<Item = (&'a str, Duration)>,
+) -> ProductivitySummary {
+    // Days recorded before the interval log only have the per app totals
+    if intervals.is_empty() {
+        ProductivitySummary::new(labels, totals.map(|(k, v)| (k, None, v)))
+    } else {
+        ProductivitySummary::new(
+            labels,
+            intervals
+                .iter()
+                .filter(|i| i.active)
+                .map(|i| (i.app_name.as_str(), i.category.as_deref(), i.duration())),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{date, interval, test_dir};
+
+    #[test]
+    fn should_calculate_range_days() {
+        // A Friday
+        let today = date(10, 16);
+        let custom = date(9, 1)..=date(9, 14);
+        assert_eq!(
+            date(10, 10)..=today,
+            DateRange::Last7Days.days(today, &custom)
+        );
+        assert_eq!(
+            date(10, 12)..=today,
+            DateRange::ThisWeek.days(today, &custom)
+        );
+        assert_eq!(
+            date(10, 1)..=today,
+            DateRange::ThisMonth.days(today, &custom)
+        );
+        assert_eq!(custom, DateRange::Custom.days(today, &custom));
+    }
+
+    #[test]
+    fn should_fill_days_without_usage_in_daily_durations() {
+        let mut history = History::default();
+        history.totals.insert(
+            date(10, 12),
+            BTreeMap::from([("chat".to_string(), Duration::from_secs(60))]),
+        );
+        history.totals.insert(
+            date(10, 14),
+            BTreeMap::from([("editor".to_string(), Duration::from_secs(60))]),
+        );
+        let totals = DashMap::new();
+        totals.insert("chat".to_string(), Duration::from_secs(30));
+        let today = Today {
+            day: date(10, 15),
+            totals: &totals,
+            intervals: &[],
+        };
+        let minutes = |d: Duration| d.as_secs() / 60;
+        assert_eq!(
+            vec![(date(10, 12), 1), (date(10, 13), 0), (date(10, 14), 0)],
+            history
+                .daily_durations(&(date(10, 1)..=date(10, 14)), "chat", &today)
+                .into_iter()
+                .map(|(day, d)| (day, minutes(d)))
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            Some(&(date(10, 15), Duration::from_secs(30))),
+            history.daily_durations(&all_days(), "chat", &today).last()
+        );
+    }
+
+    #[test]
+    fn should_only_aggregate_days_in_range() {
+        let mut history = History::default();
+        for day in [date(10, 1), date(10, 10), date(10, 15)] {
+            history.totals.insert(
+                day,
+                BTreeMap::from([("editor".to_string(), Duration::from_secs(60))]),
+            );
+        }
+        let totals = DashMap::new();
+        totals.insert("editor".to_string(), Duration::from_secs(30));
+        let today = Today {
+            day: date(10, 16),
+            totals: &totals,
+            intervals: &[],
+        };
+        let durations = history.durations(
+            &DateRange::Last7Days.days(today.day, &all_days()),
+            &today,
+            AverageMode::DaysUsed,
+            |_| true,
+        );
+        assert_eq!(
+            vec![
+                Duration::from_secs(60),
+                Duration::from_secs(60),
+                Duration::from_secs(30)
+            ],
+            durations["editor"]
+        );
+        assert_eq!(
+            4,
+            history.durations(&all_days(), &today, AverageMode::DaysUsed, |_| true)["editor"].len()
+        );
+        assert_eq!(
+            1,
+            history.durations(
+                &(date(10, 1)..=date(10, 1)),
+                &today,
+                AverageMode::DaysUsed,
+                |_| true
+            )["editor"]
+                .len()
+        );
+    }
+
+    #[test]
+    fn should_find_nothing_in_an_empty_range() {
+        let mut history = History::default();
+        history.totals.insert(
+            date(10, 14),
+            BTreeMap::from([("editor".to_string(), Duration::from_secs(60))]),
+        );
+        let totals = DashMap::new();
+        let today = Today {
+            day: date(10, 16),
+            totals: &totals,
+            intervals: &[],
+        };
+        let days = date(10, 20)..=date(10, 10);
+        assert!(history.tracked_days(&days, &today).is_empty());
+        assert!(history.intervals(&days, &today).next().is_none());
+        assert!(history.app_totals(&days, &today).is_empty());
+    }
+
+    #[test]
+    fn should_fill_days_without_usage_with_zero() {
+        let mut history = History::default();
+        // Friday, Saturday and Monday
+        for day in [date(10, 9), date(10, 10), date(10, 12)] {
+            history.totals.insert(
+                day,
+                BTreeMap::from([("editor".to_string(), Duration::from_secs(60))]),
+            );
+        }
+        history.totals.insert(
+            date(10, 13),
+            BTreeMap::from([("game".to_string(), Duration::from_secs(60))]),
+        );
+        let totals = DashMap::new();
+        let today = Today {
+            day: date(10, 14),
+            totals: &totals,
+            intervals: &[],
+        };
+        let seconds = |mode, work_days_only: bool| {
+            history
+                .durations(&all_days(), &today, mode, |day: NaiveDate| {
+                    !work_days_only || day.weekday().number_from_monday() <= 5
+                })
+                .into_iter()
+                .map(|(k, v)| (k, v.iter().map(|d| d.as_secs()).collect::<Vec<_>>()))
+                .collect::<BTreeMap<_, _>>()
+        };
+        let calendar_days = seconds(AverageMode::EveryDay, false);
+        assert_eq!(vec![60, 60, 0, 60, 0, 0], calendar_days["editor"]);
+        assert_eq!(vec![0, 0, 0, 0, 60, 0], calendar_days["game"]);
+        let work_days = seconds(AverageMode::EveryDay, true);
+        assert_eq!(vec![60, 60, 0, 0], work_days["editor"]);
+        assert_eq!(vec![0, 0, 60, 0], work_days["game"]);
+        assert_eq!(vec![60, 60], seconds(AverageMode::DaysUsed, true)["editor"]);
+    }
+
+    #[test]
+    fn should_load_only_the_range() {
+        let dir = test_dir("load-range");
+        let storage = crate::storage::JsonStorage::new(&dir, "UTC", true);
+        let interval = interval(
+            "editor",
+            None,
+            "2026-10-14T08:00:00Z",
+            "2026-10-14T08:01:00Z",
+        );
+        let totals = DashMap::new();
+        totals.insert("editor".to_string(), Duration::from_secs(60));
+        for day in [date(10, 13), date(10, 14), date(10, 15)] {
+            storage.save_day(day, &totals, std::slice::from_ref(&interval));
+        }
+        let mut warnings = vec![];
+        let history = History::load_range(
+            &storage,
+            date(10, 14)..=date(10, 15),
+            date(10, 15),
+            false,
+            &mut warnings,
+        );
+        assert_eq!(
+            vec![date(10, 14)],
+            history.totals.into_keys().collect::<Vec<_>>()
+        );
+        assert!(history.intervals.is_empty());
+        assert!(warnings.is_empty());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn should_read_the_intervals_only_for_the_ranges_looked_at() {
+        let dir = test_dir("load-intervals");
+        let storage = crate::storage::JsonStorage::new(&dir, "UTC", true);
+        let interval = interval(
+            "editor",
+            None,
+            "2026-10-14T08:00:00Z",
+            "2026-10-14T08:01:00Z",
+        );
+        for day in [date(10, 12), date(10, 13), date(10, 14), date(10, 15)] {
+            storage.save_day(day, &DashMap::new(), std::slice::from_ref(&interval));
+        }
+        let mut warnings = vec![];
+        let mut history = History::load(&storage, date(10, 15), &mut warnings);
+        assert!(history.intervals.is_empty());
+        assert!(!history.has_intervals(&(date(10, 13)..=date(10, 13))));
+
+        history.load_intervals(
+            &storage,
+            &(date(10, 13)..=date(10, 13)),
+            date(10, 15),
+            &mut warnings,
+        );
+        assert!(history.has_intervals(&(date(10, 13)..=date(10, 13))));
+        assert!(!history.has_intervals(&(date(10, 12)..=date(10, 13))));
+        history.load_intervals(
+            &storage,
+            &(date(10, 12)..=date(10, 15)),
+            date(10, 15),
+            &mut warnings,
+        );
+        assert_eq!(
+            vec![date(10, 12), date(10, 13), date(10, 14)],
+            history.intervals.keys().copied().collect::<Vec<_>>()
+        );
+        assert!(history.has_intervals(&(date(10, 12)..=date(10, 15))));
+        assert!(warnings.is_empty());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,469 @@
+use std::{collections::BTreeMap, path::Path, time::Duration};
+
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
+use chrono_tz::Tz;
+use clap::ValueEnum;
+use dashmap::DashMap;
+use serde::Deserialize;
+
+use crate::{activity::Interval, storage::Storage, utils::logical_day};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum ImportFormat {
+    /// Bucket export of ActivityWatch, only the window watcher buckets are read
+    #[default]
+    ActivityWatch,
+    /// CSV with the date, app and seconds columns
+    Csv,
+}
+
+impl ImportFormat {
+    pub const ALL: [ImportFormat; 2] = [ImportFormat::ActivityWatch, ImportFormat::Csv];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ImportFormat::ActivityWatch => "ActivityWatch",
+            ImportFormat::Csv => "CSV",
+        }
+    }
+
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some(extension) if extension.eq_ignore_ascii_case("csv") => ImportFormat::Csv,
+            _ => ImportFormat::ActivityWatch,
+        }
+    }
+}
+
+/// Data of a day read from the other tracker. ActivityWatch has the interval log, the
+/// CSV only the totals.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ImportedDay {
+    pub totals: BTreeMap<String, Duration>,
+    pub intervals: Vec<Interval>,
+}
+
+#[derive(Deserialize)]
+struct AwExport {
+    buckets: BTreeMap<String, AwBucket>,
+}
+
+#[derive(Deserialize)]
+struct AwBucket {
+    #[serde(rename = "type")]
+    bucket_type: String,
+    #[serde(default)]
+    events: Vec<AwEvent>,
+}
+
+#[derive(Deserialize)]
+struct AwEvent {
+    timestamp: DateTime<Utc>,
+    duration: f64,
+    data: AwWindow,
+}
+
+// Events of the other buckets have different data, they are read but never used
+#[derive(Deserialize)]
+struct AwWindow {
+    #[serde(default)]
+    app: String,
+    #[serde(default)]
+    title: String,
+}
+
+#[derive(Deserialize)]
+struct CsvRow {
+    date: NaiveDate,
+    app: String,
+    seconds: f64,
+}
+
+/// Days of the file, split with the configured day boundaries
+pub fn parse(
+    content: &str,
+    format: ImportFormat,
+    day_start: NaiveTime,
+    timezone: Option<Tz>,
+) -> Result<BTreeMap<NaiveDate, ImportedDay>, String> {
+    let mut days: BTreeMap<NaiveDate, ImportedDay> = BTreeMap::new();
+    match format {
+        ImportFormat::ActivityWatch => {
+            let export: AwExport = serde_json::from_str(content).map_err(|e| e.to_string())?;
+            // The AFK buckets only tell when the window events were idle, they are not
+            // used so all the window time counts as active
+            for (id, bucket) in export
+                .buckets
+                .into_iter()
+                .filter(|(_, b)| b.bucket_type == "currentwindow")
+            {
+                for event in bucket.events.into_iter().filter(|e| e.duration != 0.) {
+                    let invalid = || {
+                        format!(
+                            "bucket {}, event at {}: invalid duration",
+                            id, event.timestamp
+                        )
+                    };
+                    let duration =
+                        Duration::try_from_secs_f64(event.duration).map_err(|_| invalid())?;
+                    let end = chrono::Duration::from_std(duration)
+                        .ok()
+                        .and_then(|d| event.timestamp.checked_add_signed(d))
+                        .ok_or_else(invalid)?;
+                    let day = days
+                        .entry(logical_day(event.timestamp, day_start, timezone))
+                        .or_default();
+                    add(&mut day.totals, &event.data.app, duration).ok_or_else(invalid)?;
+                    day.intervals.push(Interval {
+                        app_name: event.data.app,
+                        title: event.data.title,
+                        start: event.timestamp,
+                        end,
+                        active: true,
+                        project: None,
+                        category: None,
+                    });
+                }
+            }
+            for day in days.values_mut() {
+                day.intervals.sort_by_key(|i| i.start);
+            }
+        }
+        ImportFormat::Csv => {
+            let mut reader = csv::Reader::from_reader(content.as_bytes());
+            for (line, row) in reader.deserialize::<CsvRow>().enumerate() {
+                // The header is the first line
+                let row = row.map_err(|e| format!("line {}: {}", line + 2, e))?;
+                let invalid = || format!("line {}: invalid seconds", line + 2);
+                let duration = Duration::try_from_secs_f64(row.seconds).map_err(|_| invalid())?;
+                add(
+                    &mut days.entry(row.date).or_default().totals,
+                    &row.app,
+                    duration,
+                )
+                .ok_or_else(invalid)?;
+            }
+        }
+    }
+    Ok(days)
+}
+
+// None when the total would overflow
+fn add(totals: &mut BTreeMap<String, Duration>, app_name: &str, duration: Duration) -> Option<()> {
+    let total = totals.entry(app_name.to_string()).or_default();
+    *total = total.checked_add(duration)?;
+    Some(())
+}
+
+/// What is going to be added to a day, without what is already recorded
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DayImport {
+    pub totals: BTreeMap<String, Duration>,
+    pub intervals: Vec<Interval>,
+    pub duplicates: usize,
+}
+
+impl DayImport {
+    /// Intervals overlapping the recorded ones were already tracked, or imported before.
+    /// Without intervals an application that already has time on the day is skipped.
+    pub fn new(
+        imported: &ImportedDay,
+        totals: &DashMap<String, Duration>,
+        intervals: &[Interval],
+    ) -> Self {
+        let mut day = Self::default();
+        if imported.intervals.is_empty() {
+            for (app_name, duration) in &imported.totals {
+                if totals.contains_key(app_name) {
+                    day.duplicates += 1;
+                } else {
+                    day.totals.insert(app_name.clone(), *duration);
+                }
+            }
+            return day;
+        }
+        for interval in &imported.intervals {
+            let overlaps = intervals
+                .iter()
+                .chain(&day.intervals)
+                .any(|i| i.start < interval.end && interval.start < i.end);
+            if overlaps {
+                day.duplicates += 1;
+            } else {
+                *day.totals.entry(interval.app_name.clone()).or_default() += interval.duration();
+                day.intervals.push(interval.clone());
+            }
+        }
+        day
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.totals.is_empty()
+    }
+}
+
+/// Preview of an import, nothing is written until it is applied
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ImportPlan {
+    pub days: BTreeMap<NaiveDate, DayImport>,
+    /// The day being tracked, and the following ones, would be overwritten by the tracker
+    pub skipped_days: Vec<NaiveDate>,
+}
+
+impl ImportPlan {
+    pub fn new(
+        imported: BTreeMap<NaiveDate, ImportedDay>,
+        storage: &dyn Storage,
+        today: NaiveDate,
+        warnings: &mut Vec<String>,
+    ) -> Self {
+        let mut plan = Self::default();
+        for (day, imported) in imported {
+            if day >= today {
+                plan.skipped_days.push(day);
+                continue;
+            }
+            let (totals, intervals) = storage.load_day(day, warnings);
+            plan.days
+                .insert(day, DayImport::new(&imported, &totals, &intervals));
+        }
+        plan
+    }
+
+    /// Adds the new data to the recorded one, the intervals are kept in order
+    pub fn apply(&self, storage: &dyn Storage, warnings: &mut Vec<String>) -> usize {
+        let mut imported = 0;
+        for (day, import) in self.days.iter().filter(|(_, d)| !d.is_empty()) {
+            let (totals, mut intervals) = storage.load_day(*day, warnings);
+            for (app_name, duration) in &import.totals {
+                *totals.entry(app_name.clone()).or_default() += *duration;
+            }
+            intervals.extend(import.intervals.iter().cloned());
+            intervals.sort_by_key(|i| i.start);
+            storage.save_day(*day, &totals, &intervals);
+            imported += 1;
+        }
+        imported
+    }
+
+    pub fn summary(&self) -> String {
+        let duration = |d: Duration| humantime::Duration::from(Duration::from_secs(d.as_secs()));
+        let mut lines = self
+            .days
+            .iter()
+            .map(|(day, import)| {
+                let mut line = format!(
+                    "{}: {} applications, {}",
+                    day,
+                    import.totals.len(),
+                    duration(import.totals.values().sum())
+                );
+                if import.duplicates > 0 {
+                    line += &format!(", {} duplicates skipped", import.duplicates);
+                }
+                line
+            })
+            .collect::<Vec<_>>();
+        for day in &self.skipped_days {
+            lines.push(format!("{}: skipped, it is the day being tracked", day));
+        }
+        let days = self.days.values().filter(|d| !d.is_empty()).count();
+        lines.push(format!(
+            "{} days to import, {} in total",
+            days,
+            duration(self.days.values().flat_map(|d| d.totals.values()).sum())
+        ));
+        lines.join("\n") + "\n"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        storage::JsonStorage,
+        test_utils::{date, interval, test_dir},
+    };
+
+    #[test]
+    fn should_parse_activity_watch_buckets() {
+        let content = r#"{"buckets": {
+            "aw-watcher-window_laptop": {
+                "id": "aw-watcher-window_laptop",
+                "type": "currentwindow",
+                "events": [
+                    {"timestamp": "2026-10-15T03:30:00+00:00", "duration": 60.5,
+                     "data": {"app": "editor", "title": "main.rs"}},
+                    {"timestamp": "2026-10-14T08:00:00+00:00", "duration": 30,
+                     "data": {"app": "editor", "title": "lib.rs"}}
+                ]
+            },
+            "aw-watcher-afk_laptop": {
+                "type": "afkstatus",
+                "events": [
+                    {"timestamp": "2026-10-14T08:00:00+00:00", "duration": 600,
+                     "data": {"status": "afk"}}
+                ]
+            }
+        }}"#;
+        // 03:30 is still the previous day with days starting at 04:00
+        let days = parse(
+            content,
+            ImportFormat::ActivityWatch,
+            NaiveTime::from_hms_opt(4, 0, 0).unwrap(),
+            Some(Tz::UTC),
+        )
+        .unwrap();
+        assert_eq!(vec![&date(10, 14)], days.keys().collect::<Vec<_>>());
+        let day = &days[&date(10, 14)];
+        assert_eq!(Duration::from_secs_f64(90.5), day.totals["editor"]);
+        assert_eq!("lib.rs", day.intervals[0].title);
+    }
+
+    #[test]
+    fn should_parse_csv() {
+        let content = "date,app,category,seconds\n\
+                       2026-10-14,editor,work,60\n\
+                       2026-10-14,editor,,30\n\
+                       2026-10-15,chat,,10\n";
+        let days = parse(content, ImportFormat::Csv, NaiveTime::MIN, None).unwrap();
+        assert_eq!(
+            Duration::from_secs(90),
+            days[&date(10, 14)].totals["editor"]
+        );
+        assert_eq!(Duration::from_secs(10), days[&date(10, 15)].totals["chat"]);
+        assert!(parse(
+            "date,app,seconds\nyesterday,chat,10\n",
+            ImportFormat::Csv,
+            NaiveTime::MIN,
+            None
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn should_reject_invalid_durations() {
+        let csv = |seconds: &str| {
+            parse(
+                &format!("date,app,seconds\n2026-10-01,editor,{}\n", seconds),
+                ImportFormat::Csv,
+                NaiveTime::MIN,
+                None,
+            )
+        };
+        for seconds in ["1e300", "NaN", "-5", "inf"] {
+            assert_eq!(Err("line 2: invalid seconds".to_string()), csv(seconds));
+        }
+        let activity_watch = |duration: &str| {
+            parse(
+                &format!(
+                    r#"{{"buckets": {{"window": {{"type": "currentwindow", "events": [
+                        {{"timestamp": "2026-10-01T08:00:00Z", "duration": {},
+                          "data": {{"app": "editor"}}}}
+                    ]}}}}}}"#,
+                    duration
+                ),
+                ImportFormat::ActivityWatch,
+                NaiveTime::MIN,
+                None,
+            )
+        };
+        assert_eq!(
+            Err("bucket window, event at 2026-10-01 08:00:00 UTC: invalid duration".to_string()),
+            activity_watch("1e300")
+        );
+        assert!(activity_watch("-5").is_err());
+        // Longer than Duration can hold once added to the timestamp
+        assert!(activity_watch("1e17").is_err());
+    }
+
+    #[test]
+    fn should_skip_duplicates() {
+        let interval = |app_name: &str, start: &str, end: &str| Interval {
+            app_name: app_name.to_string(),
+            title: String::new(),
+            start: start.parse().unwrap(),
+            end: end.parse().unwrap(),
+            active: true,
+            project: None,
+            category: None,
+        };
+        let recorded = [interval(
+            "editor",
+            "2026-10-14T08:00:00Z",
+            "2026-10-14T09:00:00Z",
+        )];
+        let totals = DashMap::new();
+        totals.insert("editor".to_string(), Duration::from_secs(3600));
+        let imported = ImportedDay {
+            totals: BTreeMap::new(),
+            intervals: vec![
+                interval("editor", "2026-10-14T08:30:00Z", "2026-10-14T08:40:00Z"),
+                interval("chat", "2026-10-14T09:00:00Z", "2026-10-14T09:10:00Z"),
+                interval("chat", "2026-10-14T09:00:00Z", "2026-10-14T09:10:00Z"),
+            ],
+        };
+        let day = DayImport::new(&imported, &totals, &recorded);
+        assert_eq!(2, day.duplicates);
+        assert_eq!(1, day.intervals.len());
+        assert_eq!(Duration::from_secs(600), day.totals["chat"]);
+
+        let imported = ImportedDay {
+            totals: BTreeMap::from([
+                ("editor".to_string(), Duration::from_secs(60)),
+                ("game".to_string(), Duration::from_secs(60)),
+            ]),
+            intervals: vec![],
+        };
+        let day = DayImport::new(&imported, &totals, &recorded);
+        assert_eq!(1, day.duplicates);
+        assert_eq!(
+            vec![&"game".to_string()],
+            day.totals.keys().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn should_import_into_days_moved_to_a_subdirectory() {
+        let dir = test_dir("import-moved-day");
+        let storage = JsonStorage::new(&dir, "UTC", true);
+        let recorded = interval(
+            "editor",
+            None,
+            "2026-10-14T08:00:00Z",
+            "2026-10-14T09:00:00Z",
+        );
+        let totals = DashMap::new();
+        totals.insert("editor".to_string(), Duration::from_secs(3600));
+        storage.save_day(date(10, 14), &totals, std::slice::from_ref(&recorded));
+        std::fs::create_dir_all(dir.join("2026/10")).unwrap();
+        for name in ["20261014", "20261014-intervals"] {
+            std::fs::rename(dir.join(name), dir.join("2026/10").join(name)).unwrap();
+        }
+
+        let mut warnings = vec![];
+        let plan = ImportPlan::new(
+            BTreeMap::from([(
+                date(10, 14),
+                ImportedDay {
+                    totals: BTreeMap::new(),
+                    intervals: vec![
+                        recorded,
+                        interval("chat", None, "2026-10-14T09:00:00Z", "2026-10-14T09:01:00Z"),
+                    ],
+                },
+            )]),
+            &storage,
+            date(10, 16),
+            &mut warnings,
+        );
+        assert_eq!(1, plan.days[&date(10, 14)].duplicates);
+        assert_eq!(1, plan.apply(&storage, &mut warnings));
+        assert!(!dir.join("20261014").exists());
+        let totals = storage.load_totals(date(10, 14)..=date(10, 14), &mut warnings);
+        assert_eq!(Duration::from_secs(3600), totals[&date(10, 14)]["editor"]);
+        assert_eq!(Duration::from_secs(60), totals[&date(10, 14)]["chat"]);
+        assert!(warnings.is_empty());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
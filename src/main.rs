@@ -2,60 +2,351 @@
 
 use std::{
     cell::RefCell,
-    collections::BTreeMap,
+    collections::BTreeSet,
+    ops::RangeInclusive,
     path::{Path, PathBuf},
     rc::Rc,
-    sync::{Arc, Mutex},
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    thread::JoinHandle,
     time::{Duration, Instant},
 };
 
 use active_win_pos_rs::{get_active_window, ActiveWindow, WindowPosition};
+use activity::{calculate_title_totals, merge_totals, record_tick, Interval, Tick};
+use api::{spawn_api_server, ApiConfig};
 use app::TimeBack;
+use calendar::{Calendar, DaySelection};
+use chrono::{NaiveDate, NaiveTime, Utc};
+use chrono_tz::Tz;
+use clap::{Args, Parser, Subcommand};
+use comparison::Comparison;
+use daemon::{lock_tracker, spawn_viewer_thread, stop_on_signal};
 use dashmap::{DashMap, DashSet};
 use device_query::{DeviceQuery, DeviceState, MouseState};
 use eframe::egui::{self};
 use egui_file_dialog::FileDialog;
-use serde::{de::DeserializeOwned, Deserialize, Serialize};
-use utils::{calculate_avg, calculate_median, calculate_sum, generate_file_name};
+use export::{export, ExportFormat};
+use heatmap::HeatmapFilter;
+use history::{AverageMode, DateRange, History, Today};
+use import::{ImportFormat, ImportPlan};
+use productivity::ProductivityLabels;
+use report::{report, Format, GroupBy, Period};
+use rules::{Rule, RuleSet};
+use serde::{Deserialize, Serialize};
+use stats::Statistic;
+use storage::{migrate_data_files, open_storage, Storage, StorageBackend};
+use table::TableSort;
+use utils::{logical_day, Cached};
 
+mod activity;
+mod api;
 mod app;
+mod calendar;
+mod comparison;
+mod daemon;
+mod export;
+mod heatmap;
+mod history;
+mod import;
+mod productivity;
+mod report;
+mod rules;
+mod stats;
+mod storage;
+mod table;
+#[cfg(test)]
+mod test_utils;
 mod utils;
 
-const INPUT_STATS_FILE: &str = "input-stats";
+const NO_OUTPUT_DIRECTORY: &str =
+    "No output directory configured, run Time back! once with the window to choose it";
 
 #[derive(Clone, Serialize, Deserialize, Debug, Default)]
 struct Config {
     output_directory: Option<String>,
     processes_with_longer_tracking: DashSet<String>,
+    #[serde(default)]
+    rules: Vec<Rule>,
+    #[serde(default)]
+    productivity: ProductivityLabels,
+    #[serde(default)]
+    day_start: NaiveTime,
+    // IANA name, the system timezone is used when missing
+    #[serde(default)]
+    timezone: Option<String>,
+    #[serde(default)]
+    storage: StorageBackend,
+    #[serde(default)]
+    calendar: Calendar,
+    #[serde(default)]
+    api: ApiConfig,
+}
+
+impl Config {
+    fn timezone(&self) -> Option<Tz> {
+        self.timezone.as_ref().and_then(|tz| tz.parse().ok())
+    }
+
+    fn current_day(&self) -> NaiveDate {
+        logical_day(Utc::now(), self.day_start, self.timezone())
+    }
+
+    fn timezone_name(&self) -> String {
+        match self.timezone() {
+            Some(timezone) => timezone.name().to_string(),
+            None => iana_time_zone::get_timezone().unwrap_or_else(|_| "Local".to_string()),
+        }
+    }
+
+    // Only the tracking process repairs the files, the others just read them
+    fn open_storage(&self, tracking: bool) -> Result<Option<Box<dyn Storage>>, String> {
+        self.output_directory
+            .as_ref()
+            .map(|dir| {
+                open_storage(
+                    Path::new(dir),
+                    self.storage,
+                    &self.timezone_name(),
+                    tracking,
+                )
+            })
+            .transpose()
+    }
+
+    // For the commands run from the terminal, which can't ask for the directory
+    fn open_required_storage(&self) -> Result<Box<dyn Storage>, String> {
+        self.open_storage(false)?
+            .ok_or_else(|| NO_OUTPUT_DIRECTORY.to_string())
+    }
+}
+
+/// Tracks the time spent on each application
+#[derive(Parser)]
+#[command(version)]
+struct Cli {
+    /// Track without the window, until SIGINT or SIGTERM
+    #[arg(long)]
+    headless: bool,
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Print the tracked time of a range of days
+    Report {
+        #[command(flatten)]
+        days: DaysArgs,
+        #[arg(long, value_enum, default_value_t)]
+        group_by: GroupBy,
+        #[arg(long, value_enum, default_value_t)]
+        format: Format,
+    },
+    /// Export the tracked time of a range of days
+    Export {
+        #[command(flatten)]
+        days: DaysArgs,
+        #[arg(long, value_enum, default_value_t)]
+        format: ExportFormat,
+        /// File to write, the standard output when missing
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+    },
+    /// Import the history of another time tracker
+    Import {
+        /// ActivityWatch bucket export or CSV with the date, app and seconds columns
+        file: PathBuf,
+        /// Guessed from the extension when missing
+        #[arg(long, value_enum)]
+        format: Option<ImportFormat>,
+        /// Only show what would be imported
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+#[derive(Args)]
+struct DaysArgs {
+    /// Days to include, today when neither the period nor the dates are given
+    #[arg(value_enum, conflicts_with_all = ["from", "to"])]
+    period: Option<Period>,
+    /// First day, YYYY-MM-DD
+    #[arg(long)]
+    from: Option<NaiveDate>,
+    /// Last day, YYYY-MM-DD, today when missing
+    #[arg(long)]
+    to: Option<NaiveDate>,
+}
+
+impl DaysArgs {
+    fn days(&self, today: NaiveDate) -> RangeInclusive<NaiveDate> {
+        match (self.period, self.from, self.to) {
+            (Some(period), _, _) => period.days(today),
+            (None, None, None) => today..=today,
+            (None, from, to) => from.unwrap_or(NaiveDate::MIN)..=to.unwrap_or(today),
+        }
+    }
+}
+
+// Release builds on Windows have no console, the commands and the headless mode print
+// in the one they were started from. Output redirected to a file is left as it is.
+#[cfg(windows)]
+fn attach_parent_console() {
+    use std::ffi::c_void;
+
+    const ATTACH_PARENT_PROCESS: u32 = u32::MAX;
+    const STD_OUTPUT_HANDLE: u32 = -11i32 as u32;
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn AttachConsole(process_id: u32) -> i32;
+        fn GetStdHandle(std_handle: u32) -> *mut c_void;
+    }
+    // SAFETY: both only read or set the handles of this process
+    unsafe {
+        if GetStdHandle(STD_OUTPUT_HANDLE).is_null() {
+            AttachConsole(ATTACH_PARENT_PROCESS);
+        }
+    }
 }
 
 fn main() -> Result<(), eframe::Error> {
+    #[cfg(windows)]
+    if std::env::args_os().len() > 1 {
+        attach_parent_console();
+    }
+    let cli = Cli::parse();
     let cfg = confy::load("time_back", None).unwrap_or_else(|e| {
         eprintln!("Failed to load configuration: {}. using default.", e);
         Config::default()
     });
 
-    let file_name = generate_file_name();
-    let (window_time, input_stats, graph_data) = if let Some(dir) = &cfg.output_directory {
-        let output_dir = Path::new(dir);
-        let current_day_file = output_dir.join(&file_name);
-        let input_stats_file = output_dir.join(INPUT_STATS_FILE);
-        let window_data: DashMap<String, Duration> = load_data_from_file(&current_day_file);
-        let input_stats_data: DashMap<String, u32> = load_data_from_file(&input_stats_file);
-        let graph_data = collect_previous_data(output_dir, &file_name).unwrap_or_default();
-        (window_data, input_stats_data, graph_data)
+    if let Some(command) = cli.command {
+        let today = cfg.current_day();
+        let result = match command {
+            Command::Report {
+                days,
+                group_by,
+                format,
+            } => {
+                let days = days.days(today);
+                // Only the categories come from the interval log
+                let intervals = group_by == GroupBy::Category;
+                with_history(&cfg, &days, intervals, |history, today_data| {
+                    report::format(
+                        &report(history, &days, today_data, group_by),
+                        &history.recorded_range(&days, today_data),
+                        group_by,
+                        format,
+                    )
+                })
+                .map(|report| print!("{}", report))
+            }
+            Command::Export {
+                days,
+                format,
+                output,
+            } => {
+                let days = days.days(today);
+                with_history(&cfg, &days, true, |history, today_data| {
+                    export(history, &days, today_data, format)
+                })
+            }
+            .and_then(|exported| match output {
+                Some(path) => std::fs::write(&path, exported)
+                    .map_err(|e| format!("Unable to write {}: {}", path.display(), e)),
+                None => {
+                    print!("{}", exported);
+                    Ok(())
+                }
+            }),
+            Command::Import {
+                file,
+                format,
+                dry_run,
+            } => run_import(&cfg, &file, format, dry_run),
+        };
+        if let Err(e) = result {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    let headless = cli.headless;
+    let mut warnings = Vec::new();
+    // Only one process records the activity, a window opened next to it is a viewer
+    let lock = match cfg
+        .output_directory
+        .as_ref()
+        .map(|dir| (dir, lock_tracker(Path::new(dir))))
+    {
+        Some((dir, Ok(lock))) => lock.map(|lock| (dir.clone(), lock)),
+        Some((_, Err(e))) => {
+            warnings.push(format!("Unable to lock the output directory: {}", e));
+            None
+        }
+        None => None,
+    };
+    // Without a directory nothing is saved yet, the tracking thread takes the lock once
+    // one is chosen
+    let tracking = lock.is_some() || cfg.output_directory.is_none();
+
+    let today = cfg.current_day();
+    if let Some(dir) = cfg.output_directory.as_ref().filter(|_| tracking) {
+        // The JSON files are also the source the database is first filled from. The
+        // tracker migrated them already when this is a viewer.
+        migrate_data_files(Path::new(dir), &cfg.timezone_name(), &mut warnings);
+    }
+    let storage = cfg.open_storage(tracking).unwrap_or_else(|e| {
+        warnings.push(format!("Unable to open the storage: {}", e));
+        None
+    });
+    let (live, history) = if let Some(storage) = storage {
+        let history = History::load(&*storage, today, &mut warnings);
+        (load_live_data(&*storage, today, &mut warnings), history)
     } else {
-        (DashMap::new(), DashMap::new(), Vec::new())
+        (
+            LiveData {
+                day: Arc::new(Mutex::new(today)),
+                ..Default::default()
+            },
+            History::default(),
+        )
     };
 
-    let shared_window_time = Arc::new(window_time);
-    let shared_input_stats = Arc::new(input_stats);
+    let shared_history = Arc::new(Mutex::new(history));
+    let api = cfg.api.clone();
     let shared_config = Arc::new(Mutex::new(cfg));
-    spawn_background_thread(
-        shared_window_time.clone(),
-        shared_input_stats.clone(),
-        shared_config.clone(),
-    );
+    // A viewer leaves the port to the tracker
+    if tracking && api.enabled {
+        if let Err(e) = spawn_api_server(
+            &api,
+            live.clone(),
+            shared_history.clone(),
+            shared_config.clone(),
+        ) {
+            warnings.push(format!("Unable to start the HTTP API: {}", e));
+        }
+    }
+    if headless {
+        return run_headless(live, shared_history, shared_config, lock, warnings);
+    }
+    let shared_tracking = Arc::new(AtomicBool::new(tracking));
+    if tracking {
+        spawn_background_thread(
+            live.clone(),
+            shared_history.clone(),
+            shared_config.clone(),
+            Arc::new(AtomicBool::new(true)),
+            shared_tracking.clone(),
+            lock,
+        );
+    } else {
+        spawn_viewer_thread(live.clone(), shared_history.clone(), shared_config.clone());
+    }
 
     let options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default().with_inner_size([800.0, 600.0]),
@@ -64,27 +355,66 @@ fn main() -> Result<(), eframe::Error> {
     };
     let close = Rc::new(RefCell::new(false));
     loop {
-        let window_time = shared_window_time.clone();
+        let live = live.clone();
         let config = shared_config.clone();
         let close_inner = close.clone();
-        let graph_data = graph_data.clone();
-        let input_stats = shared_input_stats.clone();
+        let history = shared_history.clone();
+        let tracking = shared_tracking.clone();
+        // Shown only once, not every time the window is reopened
+        let warnings = std::mem::take(&mut warnings);
         eframe::run_native(
             "Time back!",
             options.clone(),
             Box::new(move |_cc| {
                 Ok(Box::new(TimeBack {
                     file_dialog: FileDialog::new(),
+                    ics_dialog: FileDialog::new(),
+                    new_holiday: today,
+                    calendar_message: None,
+                    export_dialog: FileDialog::new(),
+                    export_open: false,
+                    export_format: ExportFormat::default(),
+                    export_message: None,
+                    import_dialog: FileDialog::new(),
+                    import_open: false,
+                    import_format: ImportFormat::default(),
+                    import_preview: None,
+                    import_message: None,
                     temp_config_path: None,
-                    window_time,
+                    live,
+                    tracking,
+                    expanded_app: None,
                     config,
                     close: close_inner,
                     show_plot: false,
                     plot_type: PlotType::Live,
-                    graph_data,
+                    history,
+                    storage: OpenStorage::new(false),
+                    date_range: DateRange::default(),
+                    custom_from: today - chrono::Days::new(13),
+                    custom_to: today,
+                    trend_apps: BTreeSet::new(),
+                    moving_average: false,
+                    moving_average_days: 7,
+                    heatmap_filter: HeatmapFilter::default(),
+                    app_names: Cached::default(),
+                    heatmap_categories: Cached::default(),
+                    heatmap: Cached::default(),
+                    productivity_scores: Cached::default(),
+                    show_statistics: false,
+                    table_sort: TableSort::default(),
+                    table_filter: String::new(),
+                    average_mode: AverageMode::default(),
+                    day_selection: DaySelection::default(),
                     settings_open: false,
                     input_stats_open: false,
-                    input_stats,
+                    timeline_open: false,
+                    comparison_open: false,
+                    comparison: Comparison::default(),
+                    timeline_day: today,
+                    settings_draft: Config::default(),
+                    productivity_names: Default::default(),
+                    warnings,
                 }))
             }),
         )?;
@@ -95,13 +425,159 @@ fn main() -> Result<(), eframe::Error> {
     Ok(())
 }
 
-fn spawn_background_thread(
+// Loads the same data the window shows, for the commands run from the terminal, only
+// for the days asked
+fn with_history(
+    config: &Config,
+    days: &RangeInclusive<NaiveDate>,
+    intervals: bool,
+    f: impl FnOnce(&History, &Today) -> Result<String, String>,
+) -> Result<String, String> {
+    let storage = config.open_required_storage()?;
+    let today = config.current_day();
+    let mut warnings = Vec::new();
+    let history = History::load_range(&*storage, days.clone(), today, intervals, &mut warnings);
+    let live = if days.contains(&today) {
+        load_live_data(&*storage, today, &mut warnings)
+    } else {
+        LiveData::default()
+    };
+    for warning in warnings {
+        eprintln!("{}", warning);
+    }
+    let activity = live.activity.lock().unwrap();
+    let today = Today {
+        day: today,
+        totals: &live.window_time,
+        intervals: &activity,
+    };
+    f(&history, &today)
+}
+
+fn run_import(
+    config: &Config,
+    path: &Path,
+    format: Option<ImportFormat>,
+    dry_run: bool,
+) -> Result<(), String> {
+    let storage = config.open_required_storage()?;
+    let format = format.unwrap_or_else(|| ImportFormat::from_path(path));
+    let mut warnings = Vec::new();
+    let plan = plan_import(config, &*storage, path, format, &mut warnings)?;
+    print!("{}", plan.summary());
+    if !dry_run {
+        println!("{} days imported", plan.apply(&*storage, &mut warnings));
+    }
+    for warning in warnings {
+        eprintln!("{}", warning);
+    }
+    Ok(())
+}
+
+fn plan_import(
+    config: &Config,
+    storage: &dyn Storage,
+    path: &Path,
+    format: ImportFormat,
+    warnings: &mut Vec<String>,
+) -> Result<ImportPlan, String> {
+    let content = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let days = import::parse(&content, format, config.day_start, config.timezone())?;
+    Ok(ImportPlan::new(
+        days,
+        storage,
+        config.current_day(),
+        warnings,
+    ))
+}
+
+fn run_headless(
+    live: LiveData,
+    history: Arc<Mutex<History>>,
+    config: Arc<Mutex<Config>>,
+    lock: Option<(String, std::fs::File)>,
+    warnings: Vec<String>,
+) -> Result<(), eframe::Error> {
+    for warning in warnings {
+        eprintln!("{}", warning);
+    }
+    if config.lock().unwrap().output_directory.is_none() {
+        eprintln!("{}", NO_OUTPUT_DIRECTORY);
+        std::process::exit(1);
+    }
+    if lock.is_none() {
+        eprintln!("Time back! is already tracking in this output directory");
+        std::process::exit(1);
+    }
+    let running = Arc::new(AtomicBool::new(true));
+    if let Err(e) = stop_on_signal(running.clone()) {
+        eprintln!("Unable to handle the termination signals: {}", e);
+        std::process::exit(1);
+    }
+    eprintln!("Tracking without a window, stop with Ctrl-C or SIGTERM");
+    let tracker = spawn_background_thread(
+        live,
+        history,
+        config,
+        running,
+        Arc::new(AtomicBool::new(true)),
+        lock,
+    );
+    if tracker.join().is_err() {
+        eprintln!("The tracking thread stopped unexpectedly");
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+// Data of the day being tracked, shared between the tracking thread and the UI
+#[derive(Clone, Default)]
+struct LiveData {
+    day: Arc<Mutex<NaiveDate>>,
     window_time: Arc<DashMap<String, Duration>>,
+    window_titles: Arc<DashMap<String, DashMap<String, Duration>>>,
+    activity: Arc<Mutex<Vec<Interval>>>,
+    // Intervals of the activity already in the storage
+    saved_intervals: Arc<AtomicUsize>,
     input_stats: Arc<DashMap<String, u32>>,
+    current_window: Arc<Mutex<ActiveWindow>>,
+}
+
+fn load_live_data(storage: &dyn Storage, day: NaiveDate, warnings: &mut Vec<String>) -> LiveData {
+    let (totals, activity) = storage.load_day(day, warnings);
+    let window_time = merge_totals(totals, &activity);
+    let window_titles = calculate_title_totals(&activity);
+    let input_stats = storage.load_input_stats(warnings);
+    LiveData {
+        day: Arc::new(Mutex::new(day)),
+        window_time: Arc::new(window_time),
+        window_titles: Arc::new(window_titles),
+        activity: Arc::new(Mutex::new(activity)),
+        saved_intervals: Arc::default(),
+        input_stats: Arc::new(input_stats),
+        current_window: Arc::default(),
+    }
+}
+
+fn spawn_background_thread(
+    live: LiveData,
+    history: Arc<Mutex<History>>,
     config: Arc<Mutex<Config>>,
-) {
-    // Collect the live data
+    running: Arc<AtomicBool>,
+    tracking: Arc<AtomicBool>,
+    lock: Option<(String, std::fs::File)>,
+) -> JoinHandle<()> {
+    let LiveData {
+        window_time,
+        window_titles,
+        activity,
+        input_stats,
+        current_window,
+        ..
+    } = live.clone();
+    // Collect the live data, until stopped when running headless
     std::thread::spawn(move || {
+        let mut storage = OpenStorage::tracker(lock);
         let mut last_input = Instant::now();
         let mut last_save = Instant::now();
         let device_state = DeviceState::new();
@@ -113,7 +589,9 @@ fn spawn_background_thread(
         let long_gap_between_input = Duration::from_secs(10 * 60);
         let small_gap_between_input = Duration::from_secs(5);
         let mut mouse_position = mouse.coords;
-        loop {
+        let mut rules = Vec::new();
+        let mut rule_set = RuleSet::new(&rules);
+        while running.load(Ordering::SeqCst) && storage.is_tracking() {
             std::thread::sleep(check_timer);
             let mouse: MouseState = device_state.get_mouse();
             let temp_position = mouse.coords;
@@ -157,114 +635,247 @@ fn spawn_background_thread(
                 },
             };
 
-            let processes_with_longer_tracking = config
-                .lock()
-                .unwrap()
-                .processes_with_longer_tracking
-                .clone();
+            let processes_with_longer_tracking = {
+                let config = config.lock().unwrap();
+                if config.rules != rules {
+                    rules = config.rules.clone();
+                    rule_set = RuleSet::new(&rules);
+                }
+                config.processes_with_longer_tracking.clone()
+            };
             let gap_between_input =
                 if processes_with_longer_tracking.contains(&active_window.app_name) {
                     long_gap_between_input
                 } else {
                     small_gap_between_input
                 };
-            if last_input.elapsed() <= gap_between_input {
-                *window_time
-                    .entry(active_window.app_name)
-                    .or_insert(Duration::default()) += check_timer;
+            let today = config.lock().unwrap().current_day();
+            let mut day = live.day.lock().unwrap();
+            // Only move forward: travelling west or a repeated DST hour could otherwise
+            // switch back to the previous day and overwrite it
+            if today > *day {
+                // Flush the finished day before starting a fresh one, otherwise its totals
+                // would end up in the new day file
+                let config = config.lock().unwrap().clone();
+                let storage = storage.get(&config);
+                if let Some(storage) = storage {
+                    save_day_data(storage, *day, &live);
+                }
+                window_time.clear();
+                window_titles.clear();
+                activity.lock().unwrap().clear();
+                live.saved_intervals.store(0, Ordering::SeqCst);
+                *day = today;
+                if let Some(storage) = storage {
+                    // Nobody looks at the console, problems are reported on the next start
+                    *history.lock().unwrap() = History::load(storage, today, &mut Vec::new());
+                }
+            }
+            drop(day);
+
+            let active = last_input.elapsed() <= gap_between_input;
+            let matched_rule = rule_set
+                .find_match(
+                    &active_window.app_name,
+                    &active_window.title,
+                    &active_window.process_path.to_string_lossy(),
+                )
+                .map(|(_, rule)| rule);
+            let recorded = record_tick(
+                &mut activity.lock().unwrap(),
+                &Tick {
+                    app_name: &active_window.app_name,
+                    title: &active_window.title,
+                    project: matched_rule
+                        .map(|r| r.project.as_str())
+                        .filter(|p| !p.is_empty()),
+                    category: matched_rule
+                        .map(|r| r.category.as_str())
+                        .filter(|c| !c.is_empty()),
+                    active,
+                },
+                chrono::Utc::now(),
+                check_timer,
+            );
+            // Keep the last window outside of Time back! so the rule preview in the
+            // settings doesn't only ever show our own window
+            if active_window.process_id != std::process::id() as u64 {
+                *current_window.lock().unwrap() = active_window.clone();
+            }
+            if active {
+                *window_titles
+                    .entry(active_window.app_name.clone())
+                    .or_default()
+                    .entry(active_window.title)
+                    .or_default() += recorded;
+                *window_time.entry(active_window.app_name).or_default() += recorded;
             }
 
             if last_save.elapsed() > save_timer {
                 last_save = Instant::now();
-                let output_directory = if let Ok(config) = config.lock() {
-                    config.output_directory.clone()
-                } else {
-                    None
-                };
-
-                if let Some(output_directory) = output_directory {
-                    let output_dir = Path::new(&output_directory);
-                    let data_file = output_dir.join(generate_file_name());
-                    let stats_file = output_dir.join(INPUT_STATS_FILE);
-                    save_data_to_file(&window_time, &data_file);
-                    save_data_to_file(&input_stats, &stats_file);
+                let config = config.lock().unwrap().clone();
+                if let Some(storage) = storage.get(&config) {
+                    save_all_data(storage, &live);
                 }
             }
         }
-    });
+        if !storage.is_tracking() {
+            tracking.store(false, Ordering::SeqCst);
+            daemon::view(live, history, config, storage);
+            return;
+        }
+        let config = config.lock().unwrap().clone();
+        if let Some(storage) = storage.get(&config) {
+            save_all_data(storage, &live);
+        }
+    })
 }
 
-fn save_data_to_file<T: Serialize>(data: &T, path: &Path) {
-    match std::fs::File::create(path) {
-        Ok(f) => {
-            if let Err(e) = serde_json::to_writer(f, &data) {
-                eprintln!("Error exporting the data: {}", e);
-            }
-        }
-        Err(e) => eprintln!("Error creating the data export file: {}", e),
-    }
+// Never call this while holding the config lock, the tracking thread locks the day first
+fn save_all_data(storage: &dyn Storage, live: &LiveData) {
+    let day = live.day.lock().unwrap();
+    save_day_data(storage, *day, live);
 }
 
-fn load_data_from_file<T: DeserializeOwned + Default>(path: &Path) -> T {
-    if path.exists() {
-        match std::fs::File::open(path) {
-            Ok(f) => serde_json::from_reader(f).unwrap_or(T::default()),
-            Err(e) => {
-                eprintln!("Failed to load the file: {:?}, {}", path, e);
-                T::default()
-            }
-        }
+// Only the intervals not saved yet are copied, the tracking and the window carry on
+// while they are written. The log is written in full on the first save of the day, or of
+// the run, dropping the intervals appended again as they grew.
+fn save_day_data(storage: &dyn Storage, day: NaiveDate, live: &LiveData) {
+    let (intervals, saved) = {
+        let activity = live.activity.lock().unwrap();
+        let saved = live
+            .saved_intervals
+            .load(Ordering::SeqCst)
+            .min(activity.len());
+        // The last saved interval may have grown since
+        (activity[saved.saturating_sub(1)..].to_vec(), saved)
+    };
+    if saved == 0 {
+        storage.save_day(day, &live.window_time, &intervals);
     } else {
-        eprintln!("Path {:?} does not exists", path);
-        T::default()
+        storage.append_day(day, &live.window_time, &intervals);
     }
+    live.saved_intervals
+        .store(saved.saturating_sub(1) + intervals.len(), Ordering::SeqCst);
+    storage.save_input_stats(&live.input_stats);
 }
 
-fn collect_previous_data(
-    output_directory: &Path,
-    current_file: &str,
-) -> Result<Vec<Vec<egui_plot::Bar>>, std::io::Error> {
-    let current_file = output_directory.join(current_file);
-    let mut values: BTreeMap<String, Vec<Duration>> = BTreeMap::new();
-    for entry in std::fs::read_dir(output_directory)? {
-        let path = entry?.path();
-        if current_file != path {
-            if let Ok(f) = std::fs::File::open(path) {
-                let data: DashMap<String, Duration> =
-                    serde_json::from_reader(f).unwrap_or_default();
-                for (k, v) in data {
-                    values.entry(k).or_default().push(v);
-                }
+fn open_configured_storage(config: &Config, tracking: bool) -> Option<Box<dyn Storage>> {
+    config.open_storage(tracking).unwrap_or_else(|e| {
+        eprintln!("Unable to open the storage: {}", e);
+        None
+    })
+}
+
+/// Storage kept open by a thread between saves, opened again when the settings change
+/// the output directory, the backend or the timezone
+struct OpenStorage {
+    tracking: bool,
+    // Lock of the directory the tracker records into, taken again when it changes
+    lock: Option<(String, std::fs::File)>,
+    opened: Option<(StorageSettings, Box<dyn Storage>)>,
+}
+
+type StorageSettings = (Option<String>, StorageBackend, Option<String>);
+
+impl OpenStorage {
+    fn new(tracking: bool) -> Self {
+        Self {
+            tracking,
+            lock: None,
+            opened: None,
+        }
+    }
+
+    fn tracker(lock: Option<(String, std::fs::File)>) -> Self {
+        Self {
+            lock,
+            ..Self::new(true)
+        }
+    }
+
+    fn is_tracking(&self) -> bool {
+        self.tracking
+    }
+
+    fn get(&mut self, config: &Config) -> Option<&dyn Storage> {
+        let settings = (
+            config.output_directory.clone(),
+            config.storage,
+            config.timezone.clone(),
+        );
+        if self
+            .opened
+            .as_ref()
+            .is_none_or(|(opened_with, _)| *opened_with != settings)
+        {
+            if self.tracking && !self.lock_directory(config) {
+                // Another process records into the new directory, only show its data
+                self.tracking = false;
+                self.lock = None;
             }
+            self.opened =
+                open_configured_storage(config, self.tracking).map(|storage| (settings, storage));
         }
+        self.opened.as_ref().map(|(_, storage)| &**storage)
     }
-    let mut result = Vec::with_capacity(PlotType::Live as usize);
-    for _ in 0..PlotType::Live as usize {
-        result.push(vec![]);
+
+    fn lock_directory(&mut self, config: &Config) -> bool {
+        let Some(dir) = &config.output_directory else {
+            return true;
+        };
+        if self.lock.as_ref().is_some_and(|(locked, _)| locked == dir) {
+            return true;
+        }
+        match lock_tracker(Path::new(dir)) {
+            Ok(Some(lock)) => {
+                self.lock = Some((dir.clone(), lock));
+                true
+            }
+            Ok(None) => {
+                eprintln!("Time back! is already tracking in {}", dir);
+                false
+            }
+            Err(e) => {
+                eprintln!("Unable to lock the output directory: {}", e);
+                false
+            }
+        }
     }
-    result[PlotType::Sum as usize] = calculate_sum(&values)
-        .into_iter()
-        .enumerate()
-        .map(|(i, (k, v))| egui_plot::Bar::new(i as f64, v).name(k))
-        .collect();
-    result[PlotType::Avg as usize] = calculate_avg(&values)
-        .into_iter()
-        .enumerate()
-        .map(|(i, (k, v))| egui_plot::Bar::new(i as f64, v).name(k))
-        .collect();
-    result[PlotType::Median as usize] = calculate_median(&values)
-        .into_iter()
-        .enumerate()
-        .map(|(i, (k, v))| egui_plot::Bar::new(i as f64, v).name(k))
-        .collect();
-    Ok(result)
 }
 
 #[derive(PartialEq)]
 enum PlotType {
-    Sum = 0,
-    Avg = 1,
-    Median = 2,
-    // Keep this as the last one as a count
+    Sum,
+    Avg,
+    Median,
+    Min,
+    Max,
+    P90,
+    P95,
+    StdDev,
+    DaysUsed,
+    Share,
+    Productivity,
+    Trend,
+    Heatmap,
     Live,
 }
+
+impl PlotType {
+    fn statistic(&self) -> Option<Statistic> {
+        match self {
+            PlotType::Sum => Some(Statistic::Sum),
+            PlotType::Avg => Some(Statistic::Avg),
+            PlotType::Median => Some(Statistic::Median),
+            PlotType::Min => Some(Statistic::Min),
+            PlotType::Max => Some(Statistic::Max),
+            PlotType::P90 => Some(Statistic::P90),
+            PlotType::P95 => Some(Statistic::P95),
+            PlotType::StdDev => Some(Statistic::StdDev),
+            PlotType::DaysUsed => Some(Statistic::DaysUsed),
+            PlotType::Share => Some(Statistic::Share),
+            PlotType::Productivity | PlotType::Trend | PlotType::Heatmap | PlotType::Live => None,
+        }
+    }
+}
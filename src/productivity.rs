@@ -0,0 +1,114 @@
+use std::{collections::BTreeMap, time::Duration};
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, Default, PartialEq, Eq)]
+pub enum Productivity {
+    Productive,
+    #[default]
+    Neutral,
+    Distracting,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, Default, PartialEq)]
+pub struct ProductivityLabels {
+    #[serde(default)]
+    pub apps: BTreeMap<String, Productivity>,
+    #[serde(default)]
+    pub categories: BTreeMap<String, Productivity>,
+}
+
+impl ProductivityLabels {
+    // Categories come from rules that can look at titles and paths, so they are more
+    // specific than the application label and win over it
+    pub fn classify(&self, app_name: &str, category: Option<&str>) -> Productivity {
+        category
+            .and_then(|c| self.categories.get(c))
+            .or_else(|| self.apps.get(app_name))
+            .copied()
+            .unwrap_or_default()
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ProductivitySummary {
+    pub productive: Duration,
+    pub neutral: Duration,
+    pub distracting: Duration,
+}
+
+impl ProductivitySummary {
+    pub fn new<'a>(
+        labels: &ProductivityLabels,
+        entries: impl Iterator<Item = (&'a str, Option<&'a str>, Duration)>,
+    ) -> Self {
+        let mut summary = Self::default();
+        for (app_name, category, duration) in entries {
+            match labels.classify(app_name, category) {
+                Productivity::Productive => summary.productive += duration,
+                Productivity::Neutral => summary.neutral += duration,
+                Productivity::Distracting => summary.distracting += duration,
+            }
+        }
+        summary
+    }
+
+    /// Score from 0 (only distracting time) to 100 (only productive time),
+    /// neutral time counts as half
+    pub fn score(&self) -> Option<f64> {
+        let total = (self.productive + self.neutral + self.distracting).as_secs_f64();
+        if total > 0. {
+            Some((self.productive.as_secs_f64() + self.neutral.as_secs_f64() / 2.) * 100. / total)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_prefer_category_label_over_app_label() {
+        let mut labels = ProductivityLabels::default();
+        labels
+            .apps
+            .insert("firefox".to_string(), Productivity::Distracting);
+        labels
+            .categories
+            .insert("work".to_string(), Productivity::Productive);
+        assert_eq!(
+            Productivity::Productive,
+            labels.classify("firefox", Some("work"))
+        );
+        assert_eq!(
+            Productivity::Distracting,
+            labels.classify("firefox", Some("news"))
+        );
+        assert_eq!(Productivity::Neutral, labels.classify("editor", None));
+    }
+
+    #[test]
+    fn should_calculate_score() {
+        let mut labels = ProductivityLabels::default();
+        labels
+            .apps
+            .insert("editor".to_string(), Productivity::Productive);
+        labels
+            .apps
+            .insert("game".to_string(), Productivity::Distracting);
+        let summary = ProductivitySummary::new(
+            &labels,
+            vec![
+                ("editor", None, Duration::from_secs(60)),
+                ("game", None, Duration::from_secs(20)),
+                ("terminal", None, Duration::from_secs(20)),
+            ]
+            .into_iter(),
+        );
+        assert_eq!(Duration::from_secs(60), summary.productive);
+        assert_eq!(Some(70.), summary.score());
+        assert_eq!(None, ProductivitySummary::default().score());
+    }
+}
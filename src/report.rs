@@ -0,0 +1,296 @@
+use std::{collections::BTreeMap, ops::RangeInclusive, time::Duration};
+
+use chrono::{Days, NaiveDate};
+use clap::ValueEnum;
+use serde::Serialize;
+
+use crate::{
+    history::{DateRange, History, Today},
+    stats::Stats,
+    storage::all_days,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Period {
+    Today,
+    Yesterday,
+    Week,
+    Month,
+    All,
+}
+
+impl Period {
+    pub fn days(&self, today: NaiveDate) -> RangeInclusive<NaiveDate> {
+        match self {
+            Period::Today => today..=today,
+            Period::Yesterday => {
+                let yesterday = today - Days::new(1);
+                yesterday..=yesterday
+            }
+            Period::Week => DateRange::ThisWeek.days(today, &all_days()),
+            Period::Month => DateRange::ThisMonth.days(today, &all_days()),
+            Period::All => all_days(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum GroupBy {
+    #[default]
+    App,
+    Day,
+    Category,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    #[default]
+    Table,
+    Json,
+    Csv,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct ReportRow {
+    pub name: String,
+    pub seconds: u64,
+    pub days: usize,
+    pub average_seconds: u64,
+    pub share: f64,
+}
+
+/// Time of each application, day or category over the range. Days and categories are
+/// listed in order, applications from the most used.
+pub fn report(
+    history: &History,
+    days: &RangeInclusive<NaiveDate>,
+    today: &Today,
+    group_by: GroupBy,
+) -> Vec<ReportRow> {
+    let mut values: BTreeMap<String, Vec<Duration>> = BTreeMap::new();
+    for day in history.tracked_days(days, today) {
+        let single_day = day..=day;
+        let totals = match group_by {
+            GroupBy::App => history.app_totals(&single_day, today),
+            GroupBy::Category => history.category_totals(&single_day, today),
+            GroupBy::Day => BTreeMap::from([(
+                day.to_string(),
+                history.app_totals(&single_day, today).values().sum(),
+            )]),
+        };
+        for (name, duration) in totals {
+            values.entry(name).or_default().push(duration);
+        }
+    }
+    let overall = values.values().flatten().sum::<Duration>();
+    let mut rows = values
+        .iter()
+        .map(|(name, values)| {
+            let stats = Stats::new(values, overall);
+            ReportRow {
+                name: name.clone(),
+                seconds: stats.sum.round() as u64,
+                days: stats.days_used,
+                average_seconds: if stats.days_used == 0 {
+                    0
+                } else {
+                    (stats.sum / stats.days_used as f64).round() as u64
+                },
+                share: (stats.share * 10.).round() / 10.,
+            }
+        })
+        .collect::<Vec<_>>();
+    if group_by == GroupBy::App {
+        rows.sort_by(|a, b| b.seconds.cmp(&a.seconds).then_with(|| a.name.cmp(&b.name)));
+    }
+    rows
+}
+
+pub fn format(
+    rows: &[ReportRow],
+    days: &RangeInclusive<NaiveDate>,
+    group_by: GroupBy,
+    format: Format,
+) -> Result<String, String> {
+    match format {
+        Format::Table => Ok(format_table(rows, group_by)),
+        Format::Json => serde_json::to_string_pretty(&serde_json::json!({
+            "from": days.start(),
+            "to": days.end(),
+            "group_by": group_name(group_by),
+            "rows": rows,
+        }))
+        .map_err(|e| e.to_string()),
+        Format::Csv => {
+            let mut writer = csv::Writer::from_writer(vec![]);
+            for row in rows {
+                writer.serialize(row).map_err(|e| e.to_string())?;
+            }
+            let bytes = writer.into_inner().map_err(|e| e.to_string())?;
+            String::from_utf8(bytes).map_err(|e| e.to_string())
+        }
+    }
+}
+
+fn group_name(group_by: GroupBy) -> &'static str {
+    match group_by {
+        GroupBy::App => "app",
+        GroupBy::Day => "day",
+        GroupBy::Category => "category",
+    }
+}
+
+fn format_table(rows: &[ReportRow], group_by: GroupBy) -> String {
+    let duration =
+        |seconds: u64| humantime::Duration::from(Duration::from_secs(seconds)).to_string();
+    let header = match group_by {
+        GroupBy::App => "Application",
+        GroupBy::Day => "Day",
+        GroupBy::Category => "Category",
+    };
+    let mut lines = vec![[
+        header.to_string(),
+        "Total".to_string(),
+        "Days".to_string(),
+        "Avg/day".to_string(),
+        "Share".to_string(),
+    ]];
+    for row in rows {
+        lines.push([
+            row.name.clone(),
+            duration(row.seconds),
+            row.days.to_string(),
+            duration(row.average_seconds),
+            format!("{:.1}%", row.share),
+        ]);
+    }
+    lines.push([
+        "Overall".to_string(),
+        duration(rows.iter().map(|r| r.seconds).sum()),
+        String::new(),
+        String::new(),
+        String::new(),
+    ]);
+    let widths = (0..5)
+        .map(|i| {
+            lines
+                .iter()
+                .map(|l| l[i].chars().count())
+                .max()
+                .unwrap_or(0)
+        })
+        .collect::<Vec<_>>();
+    lines
+        .iter()
+        .map(|line| {
+            line.iter()
+                .zip(&widths)
+                .enumerate()
+                .map(|(i, (cell, width))| {
+                    // Names on the left, numbers on the right
+                    if i == 0 {
+                        format!("{:<width$}", cell)
+                    } else {
+                        format!("{:>width$}", cell)
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join("  ")
+                .trim_end()
+                .to_string()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+        + "\n"
+}
+
+#[cfg(test)]
+mod tests {
+    use dashmap::DashMap;
+
+    use super::*;
+    use crate::test_utils::{date, interval};
+
+    fn history() -> History {
+        let mut history = History::default();
+        history.totals.insert(
+            date(10, 14),
+            BTreeMap::from([
+                ("editor".to_string(), Duration::from_secs(3000)),
+                ("chat".to_string(), Duration::from_secs(1000)),
+            ]),
+        );
+        history.intervals.insert(
+            date(10, 14),
+            vec![interval(
+                "editor",
+                Some("work"),
+                "2026-10-14T08:00:00Z",
+                "2026-10-14T08:50:00Z",
+            )],
+        );
+        history
+    }
+
+    #[test]
+    fn should_group_by_app_and_day() {
+        let history = history();
+        let totals = DashMap::new();
+        totals.insert("editor".to_string(), Duration::from_secs(1000));
+        let today = Today {
+            day: date(10, 15),
+            totals: &totals,
+            intervals: &[],
+        };
+        let days = date(10, 1)..=date(10, 15);
+        let by_app = report(&history, &days, &today, GroupBy::App);
+        assert_eq!(
+            ReportRow {
+                name: "editor".to_string(),
+                seconds: 4000,
+                days: 2,
+                average_seconds: 2000,
+                share: 80.,
+            },
+            by_app[0]
+        );
+        assert_eq!("chat", by_app[1].name);
+        let by_day = report(&history, &days, &today, GroupBy::Day);
+        assert_eq!(
+            vec![("2026-10-14", 4000), ("2026-10-15", 1000)],
+            by_day
+                .iter()
+                .map(|r| (r.name.as_str(), r.seconds))
+                .collect::<Vec<_>>()
+        );
+        let by_category = report(&history, &days, &today, GroupBy::Category);
+        assert_eq!(1, by_category.len());
+        assert_eq!(3000, by_category[0].seconds);
+        assert!(report(
+            &history,
+            &(today.day..=today.day),
+            &today,
+            GroupBy::Category
+        )
+        .is_empty());
+    }
+
+    #[test]
+    fn should_format_csv_and_table() {
+        let rows = vec![ReportRow {
+            name: "editor, the \"best\"".to_string(),
+            seconds: 3660,
+            days: 1,
+            average_seconds: 3660,
+            share: 100.,
+        }];
+        let days = date(10, 15)..=date(10, 15);
+        assert_eq!(
+            "name,seconds,days,average_seconds,share\n\"editor, the \"\"best\"\"\",3660,1,3660,100.0\n",
+            format(&rows, &days, GroupBy::App, Format::Csv).unwrap()
+        );
+        let table = format(&rows, &days, GroupBy::App, Format::Table).unwrap();
+        assert!(table.starts_with("Application         Total  Days  Avg/day   Share\n"));
+        assert!(table.ends_with("Overall             1h 1m\n"));
+    }
+}
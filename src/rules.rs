@@ -0,0 +1,156 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+// An empty pattern matches anything, a rule with only empty patterns matches every window
+#[derive(Clone, Serialize, Deserialize, Debug, Default, PartialEq)]
+pub struct Rule {
+    #[serde(default)]
+    pub app_name: String,
+    #[serde(default)]
+    pub title: String,
+    #[serde(default)]
+    pub process_path: String,
+    #[serde(default)]
+    pub project: String,
+    #[serde(default)]
+    pub category: String,
+}
+
+struct CompiledRule {
+    app_name: Option<Regex>,
+    title: Option<Regex>,
+    process_path: Option<Regex>,
+}
+
+impl CompiledRule {
+    fn matches(&self, app_name: &str, title: &str, process_path: &str) -> bool {
+        self.app_name.as_ref().is_none_or(|r| r.is_match(app_name))
+            && self.title.as_ref().is_none_or(|r| r.is_match(title))
+            && self
+                .process_path
+                .as_ref()
+                .is_none_or(|r| r.is_match(process_path))
+    }
+}
+
+pub struct RuleSet {
+    rules: Vec<(Rule, Option<CompiledRule>)>,
+    pub errors: Vec<(usize, String)>,
+}
+
+impl RuleSet {
+    pub fn new(rules: &[Rule]) -> Self {
+        let mut errors = vec![];
+        let rules = rules
+            .iter()
+            .enumerate()
+            .map(|(i, rule)| {
+                let compiled = compile_pattern(&rule.app_name).and_then(|app_name| {
+                    let title = compile_pattern(&rule.title)?;
+                    let process_path = compile_pattern(&rule.process_path)?;
+                    Ok(CompiledRule {
+                        app_name,
+                        title,
+                        process_path,
+                    })
+                });
+                match compiled {
+                    Ok(compiled) => (rule.clone(), Some(compiled)),
+                    Err(e) => {
+                        // Invalid rules never match so a typo can't swallow every window
+                        errors.push((i, e.to_string()));
+                        (rule.clone(), None)
+                    }
+                }
+            })
+            .collect();
+        Self { rules, errors }
+    }
+
+    /// Returns the first rule matching the window together with its position
+    pub fn find_match(
+        &self,
+        app_name: &str,
+        title: &str,
+        process_path: &str,
+    ) -> Option<(usize, &Rule)> {
+        self.rules
+            .iter()
+            .enumerate()
+            .find(|(_, (_, compiled))| {
+                compiled
+                    .as_ref()
+                    .is_some_and(|c| c.matches(app_name, title, process_path))
+            })
+            .map(|(i, (rule, _))| (i, rule))
+    }
+}
+
+fn compile_pattern(pattern: &str) -> Result<Option<Regex>, regex::Error> {
+    if pattern.is_empty() {
+        Ok(None)
+    } else {
+        Regex::new(pattern).map(Some)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(app_name: &str, title: &str, project: &str) -> Rule {
+        Rule {
+            app_name: app_name.to_string(),
+            title: title.to_string(),
+            project: project.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn should_return_first_matching_rule() {
+        let rules = RuleSet::new(&[
+            rule("firefox", "time_back", "time_back"),
+            rule("firefox", "", "browsing"),
+            rule("", "", "other"),
+        ]);
+        assert_eq!(
+            Some(0),
+            rules
+                .find_match("firefox", "d-cap/time_back - GitHub", "")
+                .map(|(i, _)| i)
+        );
+        assert_eq!(
+            Some(1),
+            rules.find_match("firefox", "News", "").map(|(i, _)| i)
+        );
+        assert_eq!(
+            Some(2),
+            rules.find_match("editor", "main.rs", "").map(|(i, _)| i)
+        );
+    }
+
+    #[test]
+    fn should_skip_invalid_rules() {
+        let rules = RuleSet::new(&[rule("(firefox", "", "broken"), rule("fire", "", "ok")]);
+        assert_eq!(1, rules.errors.len());
+        assert_eq!(0, rules.errors[0].0);
+        assert_eq!(
+            Some("ok"),
+            rules
+                .find_match("firefox", "", "")
+                .map(|(_, r)| r.project.as_str())
+        );
+    }
+
+    #[test]
+    fn should_match_process_path() {
+        let rules = RuleSet::new(&[Rule {
+            process_path: r"^/opt/work/".to_string(),
+            category: "work".to_string(),
+            ..Default::default()
+        }]);
+        assert!(rules.find_match("tool", "", "/opt/work/tool").is_some());
+        assert!(rules.find_match("tool", "", "/usr/bin/tool").is_none());
+    }
+}
@@ -0,0 +1,180 @@
+use std::{collections::BTreeMap, time::Duration};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Statistic {
+    Sum,
+    Avg,
+    Median,
+    Min,
+    Max,
+    P90,
+    P95,
+    StdDev,
+    DaysUsed,
+    Share,
+}
+
+/// Statistics of the daily time of an application, times are in seconds and the share
+/// is the percentage of the time of all the applications
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Stats {
+    pub sum: f64,
+    pub avg: f64,
+    pub median: f64,
+    pub min: f64,
+    pub max: f64,
+    pub p90: f64,
+    pub p95: f64,
+    pub std_dev: f64,
+    pub days_used: usize,
+    pub share: f64,
+}
+
+impl Stats {
+    pub fn new(values: &[Duration], overall: Duration) -> Self {
+        let mut sorted = values.iter().map(|v| v.as_secs_f64()).collect::<Vec<_>>();
+        if sorted.is_empty() {
+            return Self::default();
+        }
+        sorted.sort_unstable_by(f64::total_cmp);
+        let sum = sorted.iter().sum::<f64>();
+        let avg = sum / sorted.len() as f64;
+        let variance = sorted.iter().map(|v| (v - avg).powi(2)).sum::<f64>() / sorted.len() as f64;
+        Self {
+            sum,
+            avg,
+            median: percentile(&sorted, 50.),
+            min: sorted[0],
+            max: sorted[sorted.len() - 1],
+            p90: percentile(&sorted, 90.),
+            p95: percentile(&sorted, 95.),
+            std_dev: variance.sqrt(),
+            days_used: sorted.iter().filter(|v| **v > 0.).count(),
+            share: if overall.is_zero() {
+                0.
+            } else {
+                sum * 100. / overall.as_secs_f64()
+            },
+        }
+    }
+
+    pub fn get(&self, statistic: Statistic) -> f64 {
+        match statistic {
+            Statistic::Sum => self.sum,
+            Statistic::Avg => self.avg,
+            Statistic::Median => self.median,
+            Statistic::Min => self.min,
+            Statistic::Max => self.max,
+            Statistic::P90 => self.p90,
+            Statistic::P95 => self.p95,
+            Statistic::StdDev => self.std_dev,
+            Statistic::DaysUsed => self.days_used as f64,
+            Statistic::Share => self.share,
+        }
+    }
+}
+
+/// Percentile of sorted values, interpolating between the two closest ones
+pub fn percentile(sorted: &[f64], percentile: f64) -> f64 {
+    if sorted.is_empty() {
+        return 0.;
+    }
+    let position = percentile.clamp(0., 100.) / 100. * (sorted.len() - 1) as f64;
+    let lower = position.floor() as usize;
+    let upper = position.ceil() as usize;
+    sorted[lower] + (sorted[upper] - sorted[lower]) * (position - lower as f64)
+}
+
+pub fn calculate(data: &BTreeMap<String, Vec<Duration>>) -> BTreeMap<&str, Stats> {
+    let overall = data.values().flatten().sum::<Duration>();
+    data.iter()
+        .map(|(k, v)| (k.as_str(), Stats::new(v, overall)))
+        .collect()
+}
+
+/// Applications sorted by the statistic, highest first
+pub fn rank(data: &BTreeMap<String, Vec<Duration>>, statistic: Statistic) -> Vec<(&str, f64)> {
+    let mut result = calculate(data)
+        .into_iter()
+        .map(|(k, stats)| (k, stats.get(statistic)))
+        .collect::<Vec<_>>();
+    result.sort_by(|a, b| b.1.total_cmp(&a.1));
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn seconds(values: &[u64]) -> Vec<Duration> {
+        values.iter().map(|v| Duration::from_secs(*v)).collect()
+    }
+
+    #[test]
+    fn should_calculate_median_with_no_data() {
+        let data: BTreeMap<String, Vec<Duration>> = BTreeMap::new();
+        let vec: Vec<(&str, f64)> = vec![];
+        assert_eq!(vec, rank(&data, Statistic::Median));
+    }
+
+    #[test]
+    fn should_calculate_median_with_single_data_odd() {
+        let mut data: BTreeMap<String, Vec<Duration>> = BTreeMap::new();
+        data.insert("time_back".to_string(), seconds(&[1, 15, 40, 2, 4]));
+        let vec = vec![("time_back", 4.0)];
+        assert_eq!(vec, rank(&data, Statistic::Median));
+    }
+
+    #[test]
+    fn should_calculate_median_with_single_data_even() {
+        let mut data: BTreeMap<String, Vec<Duration>> = BTreeMap::new();
+        data.insert("time_back".to_string(), seconds(&[5, 1, 15, 40, 2, 4]));
+        let vec = vec![("time_back", 4.5)];
+        assert_eq!(vec, rank(&data, Statistic::Median));
+    }
+
+    #[test]
+    fn should_calculate_median_with_multiple_odd_data() {
+        let mut data: BTreeMap<String, Vec<Duration>> = BTreeMap::new();
+        data.insert("time_back".to_string(), seconds(&[5, 1, 15, 40, 2, 4]));
+        data.insert("something-else".to_string(), seconds(&[4, 10, 1, 20, 41]));
+        let vec = vec![("something-else", 10.), ("time_back", 4.5)];
+        assert_eq!(vec, rank(&data, Statistic::Median));
+    }
+
+    #[test]
+    fn should_calculate_min_max_and_percentiles() {
+        let stats = Stats::new(
+            &seconds(&[10, 0, 20, 30, 40, 50, 60, 70, 80, 90, 100]),
+            Duration::ZERO,
+        );
+        assert_eq!(0., stats.min);
+        assert_eq!(100., stats.max);
+        assert_eq!(50., stats.median);
+        assert_eq!(90., stats.p90);
+        assert_eq!(95., stats.p95);
+        assert_eq!(10, stats.days_used);
+    }
+
+    #[test]
+    fn should_calculate_std_dev() {
+        let stats = Stats::new(&seconds(&[2, 4, 4, 4, 5, 5, 7, 9]), Duration::ZERO);
+        assert_eq!(5., stats.avg);
+        assert_eq!(2., stats.std_dev);
+    }
+
+    #[test]
+    fn should_calculate_share_of_total() {
+        let mut data: BTreeMap<String, Vec<Duration>> = BTreeMap::new();
+        data.insert("time_back".to_string(), seconds(&[30, 45]));
+        data.insert("something-else".to_string(), seconds(&[25]));
+        assert_eq!(
+            vec![("time_back", 75.), ("something-else", 25.)],
+            rank(&data, Statistic::Share)
+        );
+        assert_eq!(
+            vec![("time_back", 75.), ("something-else", 25.)],
+            rank(&data, Statistic::Sum)
+        );
+    }
+}
@@ -0,0 +1,83 @@
+use std::{collections::BTreeMap, ops::RangeInclusive, path::Path, time::Duration};
+
+use chrono::NaiveDate;
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+
+use crate::activity::Interval;
+
+mod json;
+mod sqlite;
+
+pub use json::{migrate_data_files, JsonStorage};
+pub use sqlite::SqliteStorage;
+
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, Default, PartialEq, Eq)]
+pub enum StorageBackend {
+    #[default]
+    Json,
+    Sqlite,
+}
+
+// Loading never fails: problems are reported in the warnings and the data that could be
+// read is returned. Saving problems are only logged as the data is saved again shortly.
+pub trait Storage: Send + Sync {
+    /// Totals and interval log of the day being tracked
+    fn load_day(
+        &self,
+        day: NaiveDate,
+        warnings: &mut Vec<String>,
+    ) -> (DashMap<String, Duration>, Vec<Interval>);
+
+    /// Totals and interval log of the day, replacing what was saved
+    fn save_day(&self, day: NaiveDate, totals: &DashMap<String, Duration>, intervals: &[Interval]);
+
+    /// Totals of the day being tracked and the intervals recorded since the last save.
+    /// The first one can be the last saved interval, grown since, that it replaces.
+    fn append_day(
+        &self,
+        day: NaiveDate,
+        totals: &DashMap<String, Duration>,
+        intervals: &[Interval],
+    );
+
+    /// Per application totals of every recorded day in the range
+    fn load_totals(
+        &self,
+        days: RangeInclusive<NaiveDate>,
+        warnings: &mut Vec<String>,
+    ) -> BTreeMap<NaiveDate, BTreeMap<String, Duration>>;
+
+    /// Interval logs of the days in the range, days recorded before the log existed are missing
+    fn load_intervals(
+        &self,
+        days: RangeInclusive<NaiveDate>,
+        warnings: &mut Vec<String>,
+    ) -> BTreeMap<NaiveDate, Vec<Interval>>;
+
+    fn load_input_stats(&self, warnings: &mut Vec<String>) -> DashMap<String, u32>;
+
+    fn save_input_stats(&self, input_stats: &DashMap<String, u32>);
+}
+
+pub fn open_storage(
+    output_directory: &Path,
+    backend: StorageBackend,
+    timezone: &str,
+    tracking: bool,
+) -> Result<Box<dyn Storage>, String> {
+    match backend {
+        StorageBackend::Json => Ok(Box::new(JsonStorage::new(
+            output_directory,
+            timezone,
+            tracking,
+        ))),
+        StorageBackend::Sqlite => SqliteStorage::open(output_directory, timezone)
+            .map(|storage| Box::new(storage) as Box<dyn Storage>)
+            .map_err(|e| e.to_string()),
+    }
+}
+
+pub fn all_days() -> RangeInclusive<NaiveDate> {
+    NaiveDate::MIN..=NaiveDate::MAX
+}
@@ -0,0 +1,674 @@
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::{BufReader, BufWriter, Read, Write},
+    ops::RangeInclusive,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use chrono::NaiveDate;
+use dashmap::DashMap;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::{
+    activity::Interval,
+    utils::{day_file_name, is_day_file_name, parse_day_file_name},
+};
+
+use super::Storage;
+
+const INPUT_STATS_FILE: &str = "input-stats";
+const INTERVALS_FILE_SUFFIX: &str = "-intervals";
+const TEMP_EXTENSION: &str = "tmp";
+const BACKUP_EXTENSION: &str = "bak";
+const LEGACY_BACKUP_EXTENSION: &str = "v1.bak";
+const SCHEMA_VERSION: u32 = 2;
+
+// Every data file is wrapped in this, files written before it existed are version 1
+#[derive(Serialize, Deserialize)]
+struct Envelope<T> {
+    schema_version: u32,
+    host: String,
+    timezone: String,
+    app_version: String,
+    data: T,
+}
+
+impl<T> Envelope<T> {
+    fn new(data: T, timezone: &str) -> Self {
+        Self {
+            schema_version: SCHEMA_VERSION,
+            host: gethostname::gethostname().to_string_lossy().into_owned(),
+            timezone: timezone.to_string(),
+            app_version: env!("CARGO_PKG_VERSION").to_string(),
+            data,
+        }
+    }
+}
+
+// One `YYYYMMDD` totals file and one `YYYYMMDD-intervals` file per day, plus the
+// `input-stats` file, in the output directory. Past days can also be moved to its
+// subdirectories.
+pub struct JsonStorage {
+    directory: PathBuf,
+    timezone: String,
+    // Only the tracking process moves the corrupted files away, the others could rename
+    // them under its feet
+    tracking: bool,
+}
+
+impl JsonStorage {
+    pub fn new(directory: &Path, timezone: &str, tracking: bool) -> Self {
+        Self {
+            directory: directory.to_path_buf(),
+            timezone: timezone.to_string(),
+            tracking,
+        }
+    }
+
+    fn totals_path(&self, day: NaiveDate) -> PathBuf {
+        self.directory.join(day_file_name(day))
+    }
+
+    fn intervals_path(&self, day: NaiveDate) -> PathBuf {
+        self.directory
+            .join(day_file_name(day) + INTERVALS_FILE_SUFFIX)
+    }
+
+    // The day being tracked is in the output directory itself, a past day may have been
+    // moved to a subdirectory since and is read and rewritten there. The directory is
+    // only searched when the day has no file in the output directory.
+    fn day_path(&self, day: NaiveDate, intervals: bool) -> PathBuf {
+        let path = if intervals {
+            self.intervals_path(day)
+        } else {
+            self.totals_path(day)
+        };
+        if path.exists() || path.with_extension(BACKUP_EXTENSION).exists() {
+            return path;
+        }
+        self.recorded_days(&(day..=day), intervals, &mut Vec::new())
+            .remove(&day)
+            .unwrap_or(path)
+    }
+
+    fn input_stats_path(&self) -> PathBuf {
+        self.directory.join(INPUT_STATS_FILE)
+    }
+
+    // Days in the range with a totals file, or with an intervals file. Only the names
+    // decide what is a day file, anything else the directory contains is left alone.
+    fn recorded_days(
+        &self,
+        days: &RangeInclusive<NaiveDate>,
+        intervals: bool,
+        warnings: &mut Vec<String>,
+    ) -> BTreeMap<NaiveDate, PathBuf> {
+        let mut files = vec![];
+        if let Err(e) = find_files(&self.directory, &mut files) {
+            warnings.push(format!("Unable to read {:?}: {}", self.directory, e));
+        }
+        let mut result: BTreeMap<NaiveDate, PathBuf> = BTreeMap::new();
+        for path in files {
+            let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+                continue;
+            };
+            let name = if intervals {
+                match name.strip_suffix(INTERVALS_FILE_SUFFIX) {
+                    Some(name) => name,
+                    None => continue,
+                }
+            } else {
+                name
+            };
+            if !is_day_file_name(name) {
+                continue;
+            }
+            let Some(day) = parse_day_file_name(name) else {
+                warnings.push(format!(
+                    "{:?} is named like a day file but {} is not a date",
+                    path, name
+                ));
+                continue;
+            };
+            if !days.contains(&day) {
+                continue;
+            }
+            // The day being tracked is always written in the output directory itself
+            let in_root = path.parent() == Some(self.directory.as_path());
+            if let Some(previous) = result.get(&day) {
+                let (used, ignored) = if in_root {
+                    (&path, previous)
+                } else {
+                    (previous, &path)
+                };
+                warnings.push(format!(
+                    "{} is recorded in both {:?} and {:?}, only {:?} is used",
+                    day, used, ignored, used
+                ));
+                if !in_root {
+                    continue;
+                }
+            }
+            result.insert(day, path);
+        }
+        result
+    }
+}
+
+// Every file in the directory and its subdirectories, so the history can be organized
+// by year and month (e.g. `2026/10/20261016`)
+fn find_files(dir: &Path, files: &mut Vec<PathBuf>) -> Result<(), std::io::Error> {
+    let mut entries = std::fs::read_dir(dir)?
+        .filter_map(|e| e.ok())
+        .collect::<Vec<_>>();
+    entries.sort_by_key(|e| e.file_name());
+    for entry in entries {
+        let path = entry.path();
+        // Symlinks are not followed so a link to a parent can't loop forever
+        match entry.file_type() {
+            Ok(t) if t.is_dir() => find_files(&path, files)?,
+            Ok(t) if t.is_file() => files.push(path),
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+impl Storage for JsonStorage {
+    fn load_day(
+        &self,
+        day: NaiveDate,
+        warnings: &mut Vec<String>,
+    ) -> (DashMap<String, Duration>, Vec<Interval>) {
+        (
+            load_data_from_file(&self.day_path(day, false), self.tracking, warnings),
+            load_file(
+                &self.day_path(day, true),
+                self.tracking,
+                warnings,
+                read_interval_log,
+            ),
+        )
+    }
+
+    fn save_day(&self, day: NaiveDate, totals: &DashMap<String, Duration>, intervals: &[Interval]) {
+        save_data_to_file(&totals, &self.day_path(day, false), &self.timezone);
+        save_data_to_file(&intervals, &self.day_path(day, true), &self.timezone);
+    }
+
+    fn append_day(
+        &self,
+        day: NaiveDate,
+        totals: &DashMap<String, Duration>,
+        intervals: &[Interval],
+    ) {
+        save_data_to_file(&totals, &self.day_path(day, false), &self.timezone);
+        let path = self.day_path(day, true);
+        if !path.exists() {
+            save_data_to_file(&intervals, &path, &self.timezone);
+        } else if let Err(e) = append_to_log(intervals, &path) {
+            eprintln!("Error exporting the data to {:?}: {}", path, e);
+        }
+    }
+
+    fn load_totals(
+        &self,
+        days: RangeInclusive<NaiveDate>,
+        warnings: &mut Vec<String>,
+    ) -> BTreeMap<NaiveDate, BTreeMap<String, Duration>> {
+        self.recorded_days(&days, false, warnings)
+            .into_iter()
+            .filter_map(|(day, path)| match read_data_file(&path) {
+                Ok(data) => Some((day, data)),
+                Err(e) => {
+                    warnings.push(format!(
+                        "{:?} is not a valid day file, skipped: {}",
+                        path, e
+                    ));
+                    None
+                }
+            })
+            .collect()
+    }
+
+    fn load_intervals(
+        &self,
+        days: RangeInclusive<NaiveDate>,
+        warnings: &mut Vec<String>,
+    ) -> BTreeMap<NaiveDate, Vec<Interval>> {
+        self.recorded_days(&days, true, warnings)
+            .into_iter()
+            .map(|(day, path)| {
+                let intervals = load_file(&path, self.tracking, warnings, read_interval_log);
+                (day, intervals)
+            })
+            .collect()
+    }
+
+    fn load_input_stats(&self, warnings: &mut Vec<String>) -> DashMap<String, u32> {
+        load_data_from_file(&self.input_stats_path(), self.tracking, warnings)
+    }
+
+    fn save_input_stats(&self, input_stats: &DashMap<String, u32>) {
+        save_data_to_file(&input_stats, &self.input_stats_path(), &self.timezone);
+    }
+}
+
+fn save_data_to_file<T: Serialize>(data: &T, path: &Path, timezone: &str) {
+    if let Err(e) = write_data_atomically(&Envelope::new(data, timezone), path) {
+        eprintln!("Error exporting the data to {:?}: {}", path, e);
+    }
+}
+
+// The data is written to a temporary file that replaces the real one only once it is
+// complete and on disk, the previous generation is kept as a backup
+fn write_data_atomically<T: Serialize>(data: &T, path: &Path) -> Result<(), std::io::Error> {
+    let temp_path = path.with_extension(TEMP_EXTENSION);
+    let mut writer = BufWriter::new(File::create(&temp_path)?);
+    serde_json::to_writer(&mut writer, data)?;
+    writer
+        .into_inner()
+        .map_err(|e| e.into_error())?
+        .sync_all()?;
+    if path.exists() {
+        // The backup is another name for the previous file, not the file moved away, so
+        // the day file is replaced in a single step and never goes missing
+        let backup_path = path.with_extension(BACKUP_EXTENSION);
+        match std::fs::remove_file(&backup_path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e),
+            _ => {}
+        }
+        if std::fs::hard_link(path, &backup_path).is_err() {
+            std::fs::copy(path, &backup_path)?;
+        }
+    }
+    std::fs::rename(&temp_path, path)?;
+    #[cfg(unix)]
+    if let Some(dir) = path.parent() {
+        File::open(dir)?.sync_all()?;
+    }
+    Ok(())
+}
+
+// Each interval on a line of its own after the log written in full, synced as the log
+// itself is never replaced while the day is tracked
+fn append_to_log(intervals: &[Interval], path: &Path) -> Result<(), std::io::Error> {
+    let mut writer = BufWriter::new(File::options().append(true).open(path)?);
+    for interval in intervals {
+        writeln!(writer)?;
+        serde_json::to_writer(&mut writer, interval)?;
+    }
+    writer.into_inner().map_err(|e| e.into_error())?.sync_data()
+}
+
+// The log written in full, followed by the intervals appended since. An appended interval
+// replaces the ones starting with it or after, the last interval is appended again every
+// time it grows.
+fn read_interval_log(path: &Path) -> Result<Vec<Interval>, std::io::Error> {
+    let mut values = serde_json::Deserializer::from_reader(BufReader::new(File::open(path)?))
+        .into_iter::<serde_json::Value>();
+    let first = values.next().ok_or_else(|| {
+        std::io::Error::new(std::io::ErrorKind::UnexpectedEof, "the file is empty")
+    })??;
+    let mut intervals: Vec<Interval> = serde_json::from_value(unwrap_envelope(first)?)?;
+    for value in values {
+        let interval: Interval = match value.and_then(serde_json::from_value) {
+            Ok(interval) => interval,
+            // Cut short while being appended, the tracker writes the log in full again
+            // when it starts
+            Err(e) if e.is_eof() => break,
+            Err(e) => return Err(e.into()),
+        };
+        let replaced = intervals.partition_point(|i| i.start < interval.start);
+        intervals.truncate(replaced);
+        intervals.push(interval);
+    }
+    Ok(intervals)
+}
+
+fn read_data_file<T: DeserializeOwned>(path: &Path) -> Result<T, std::io::Error> {
+    let value: serde_json::Value = serde_json::from_reader(BufReader::new(File::open(path)?))?;
+    Ok(serde_json::from_value(unwrap_envelope(value)?)?)
+}
+
+fn is_envelope(value: &serde_json::Value) -> bool {
+    value.get("schema_version").is_some_and(|v| v.is_u64()) && value.get("data").is_some()
+}
+
+// Legacy files are the bare data
+fn unwrap_envelope(value: serde_json::Value) -> Result<serde_json::Value, std::io::Error> {
+    if !is_envelope(&value) {
+        return Ok(value);
+    }
+    let envelope: Envelope<serde_json::Value> = serde_json::from_value(value)?;
+    if envelope.schema_version > SCHEMA_VERSION {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!(
+                "written by Time back! {} with schema version {}, this version only supports {}",
+                envelope.app_version, envelope.schema_version, SCHEMA_VERSION
+            ),
+        ));
+    }
+    Ok(envelope.data)
+}
+
+fn is_data_file(name: &str) -> bool {
+    name == INPUT_STATS_FILE
+        || parse_day_file_name(name.strip_suffix(INTERVALS_FILE_SUFFIX).unwrap_or(name)).is_some()
+}
+
+// Wraps the files written before the schema was versioned in the envelope, keeping a
+// copy of the original next to them
+pub fn migrate_data_files(output_dir: &Path, timezone: &str, warnings: &mut Vec<String>) {
+    let mut files = vec![];
+    if let Err(e) = find_files(output_dir, &mut files) {
+        warnings.push(format!("Unable to migrate {:?}: {}", output_dir, e));
+        return;
+    }
+    for path in files {
+        if !path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .is_some_and(is_data_file)
+        {
+            continue;
+        }
+        if let Err(e) = migrate_data_file(&path, timezone) {
+            warnings.push(format!("Unable to migrate {:?}: {}", path, e));
+        }
+    }
+}
+
+fn migrate_data_file(path: &Path, timezone: &str) -> Result<(), std::io::Error> {
+    if starts_with_envelope(path)? {
+        return Ok(());
+    }
+    let value: serde_json::Value = serde_json::from_reader(BufReader::new(File::open(path)?))?;
+    std::fs::copy(path, path.with_extension(LEGACY_BACKUP_EXTENSION))?;
+    write_data_atomically(&Envelope::new(value, timezone), path)
+}
+
+// The envelope is always written with the schema version first, so the beginning of the
+// file is enough to know whether it is already migrated
+fn starts_with_envelope(path: &Path) -> Result<bool, std::io::Error> {
+    let mut start = vec![];
+    File::open(path)?.take(64).read_to_end(&mut start)?;
+    Ok(String::from_utf8_lossy(&start)
+        .trim_start()
+        .starts_with("{\"schema_version\":"))
+}
+
+// A missing file is a new day, anything else that prevents reading it is reported and
+// the backup is used instead, so a bad write doesn't silently wipe the data. The
+// corrupted file is moved away when `quarantine` is set, otherwise it is left in place.
+fn load_data_from_file<T: DeserializeOwned + Default>(
+    path: &Path,
+    quarantine: bool,
+    warnings: &mut Vec<String>,
+) -> T {
+    load_file(path, quarantine, warnings, read_data_file)
+}
+
+fn load_file<T: Default>(
+    path: &Path,
+    quarantine: bool,
+    warnings: &mut Vec<String>,
+    read: fn(&Path) -> Result<T, std::io::Error>,
+) -> T {
+    let backup_path = path.with_extension(BACKUP_EXTENSION);
+    if !path.exists() {
+        if backup_path.exists() {
+            // Left by a version that moved the file to the backup before putting the new
+            // one in place, and crashed in between
+            warnings.push(format!("{:?} is missing, restored from its backup", path));
+            return load_backup(&backup_path, warnings, read);
+        }
+        return T::default();
+    }
+    match read(path) {
+        Ok(data) => data,
+        Err(e) if !quarantine => {
+            warnings.push(format!("{:?} is corrupted ({}), skipped", path, e));
+            load_backup(&backup_path, warnings, read)
+        }
+        Err(e) => {
+            let corrupted_path =
+                path.with_extension(format!("corrupted-{}", chrono::Utc::now().timestamp()));
+            let moved = match std::fs::rename(path, &corrupted_path) {
+                Ok(_) => format!("moved to {:?}", corrupted_path),
+                Err(e) => format!("could not be moved away: {}", e),
+            };
+            warnings.push(format!("{:?} is corrupted ({}), {}", path, e, moved));
+            load_backup(&backup_path, warnings, read)
+        }
+    }
+}
+
+fn load_backup<T: Default>(
+    backup_path: &Path,
+    warnings: &mut Vec<String>,
+    read: fn(&Path) -> Result<T, std::io::Error>,
+) -> T {
+    match read(backup_path) {
+        Ok(data) => {
+            warnings.push(format!("Data restored from {:?}", backup_path));
+            data
+        }
+        Err(e) => {
+            warnings.push(format!(
+                "The backup {:?} is not usable either ({}), starting empty",
+                backup_path, e
+            ));
+            T::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        storage::all_days,
+        test_utils::{date, interval, test_dir},
+    };
+
+    fn copy_fixtures(fixtures: &str, dir: &Path) {
+        let fixtures = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures")
+            .join(fixtures);
+        for entry in std::fs::read_dir(fixtures).unwrap() {
+            let path = entry.unwrap().path();
+            std::fs::copy(&path, dir.join(path.file_name().unwrap())).unwrap();
+        }
+    }
+
+    #[test]
+    fn should_migrate_legacy_files() {
+        let dir = test_dir("migrate");
+        copy_fixtures("legacy", &dir);
+        let mut warnings = vec![];
+        migrate_data_files(&dir, "Europe/Rome", &mut warnings);
+        assert!(warnings.is_empty());
+
+        let day_file = dir.join("20240101");
+        let envelope: Envelope<serde_json::Value> =
+            serde_json::from_reader(File::open(&day_file).unwrap()).unwrap();
+        assert_eq!(SCHEMA_VERSION, envelope.schema_version);
+        assert_eq!("Europe/Rome", envelope.timezone);
+        let data: DashMap<String, Duration> = load_data_from_file(&day_file, true, &mut warnings);
+        assert_eq!(Duration::from_secs(3600), *data.get("firefox").unwrap());
+        assert_eq!(Duration::from_millis(7200500), *data.get("code").unwrap());
+        let legacy: DashMap<String, Duration> =
+            read_data_file(&day_file.with_extension(LEGACY_BACKUP_EXTENSION)).unwrap();
+        assert_eq!(2, legacy.len());
+
+        let stats: DashMap<String, u32> =
+            load_data_from_file(&dir.join(INPUT_STATS_FILE), true, &mut warnings);
+        assert_eq!(4000, *stats.get("Mouse move").unwrap());
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn should_not_migrate_versioned_files() {
+        let dir = test_dir("versioned");
+        copy_fixtures("versioned", &dir);
+        // Only the beginning is read
+        std::fs::write(
+            dir.join("20261014-intervals"),
+            "{\"schema_version\":2,\"data\":[]}\n{\"app_name\":",
+        )
+        .unwrap();
+        let mut warnings = vec![];
+        migrate_data_files(&dir, "UTC", &mut warnings);
+        assert!(warnings.is_empty());
+        assert!(!dir
+            .join("20261015")
+            .with_extension(LEGACY_BACKUP_EXTENSION)
+            .exists());
+        let data: DashMap<String, Duration> =
+            load_data_from_file(&dir.join("20261015"), true, &mut warnings);
+        assert_eq!(Duration::from_secs(60), *data.get("firefox").unwrap());
+    }
+
+    #[test]
+    fn should_refuse_files_from_newer_versions() {
+        let dir = test_dir("newer");
+        copy_fixtures("versioned", &dir);
+        assert!(read_data_file::<DashMap<String, Duration>>(&dir.join("20261016")).is_err());
+    }
+
+    #[test]
+    fn should_keep_previous_generation_as_backup() {
+        let dir = test_dir("backup");
+        let path = dir.join("20261016");
+        save_data_to_file(&vec![1], &path, "UTC");
+        save_data_to_file(&vec![2], &path, "UTC");
+        save_data_to_file(&vec![3], &path, "UTC");
+        let mut warnings = vec![];
+        assert_eq!(
+            vec![3],
+            load_data_from_file::<Vec<u32>>(&path, true, &mut warnings)
+        );
+        assert_eq!(
+            vec![2],
+            read_data_file::<Vec<u32>>(&path.with_extension("bak")).unwrap()
+        );
+        assert!(!path.with_extension("tmp").exists());
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn should_recover_corrupted_file_from_backup() {
+        let dir = test_dir("corrupted");
+        let path = dir.join("20261016");
+        save_data_to_file(&vec![1], &path, "UTC");
+        save_data_to_file(&vec![2], &path, "UTC");
+        std::fs::write(&path, "[2, 3").unwrap();
+        let mut warnings = vec![];
+        // Only read next to the tracker
+        assert_eq!(
+            vec![1],
+            load_data_from_file::<Vec<u32>>(&path, false, &mut warnings)
+        );
+        assert_eq!(2, warnings.len());
+        assert!(path.exists());
+        warnings.clear();
+        assert_eq!(
+            vec![1],
+            load_data_from_file::<Vec<u32>>(&path, true, &mut warnings)
+        );
+        assert_eq!(2, warnings.len());
+        assert!(!path.exists());
+        let corrupted = std::fs::read_dir(&dir)
+            .unwrap()
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_name().to_string_lossy().contains("corrupted"))
+            .count();
+        assert_eq!(1, corrupted);
+    }
+
+    #[test]
+    fn should_only_load_day_files_as_history() {
+        let dir = test_dir("history");
+        let storage = JsonStorage::new(&dir, "UTC", true);
+        let totals = DashMap::new();
+        totals.insert("editor".to_string(), Duration::from_secs(60));
+        let day = NaiveDate::from_ymd_opt(2026, 10, 16).unwrap();
+        storage.save_day(day, &totals, &[]);
+        storage.save_day(day, &totals, &[]);
+        storage.save_input_stats(&DashMap::new());
+        std::fs::create_dir_all(dir.join("2026/10")).unwrap();
+        save_data_to_file(&totals, &dir.join("2026/10/20261015"), "UTC");
+        std::fs::write(dir.join(".20261016.swp"), "swap").unwrap();
+        std::fs::write(dir.join("notes"), "notes").unwrap();
+        std::fs::write(dir.join("20261014"), "{").unwrap();
+        std::fs::write(dir.join("20261399"), "{}").unwrap();
+
+        let mut warnings = vec![];
+        let history = storage.load_totals(all_days(), &mut warnings);
+        assert_eq!(
+            vec![NaiveDate::from_ymd_opt(2026, 10, 15).unwrap(), day],
+            history.keys().copied().collect::<Vec<_>>()
+        );
+        assert_eq!(2, warnings.len());
+        assert!(warnings.iter().any(|w| w.contains("20261014")));
+        assert!(warnings.iter().any(|w| w.contains("20261399")));
+    }
+
+    #[test]
+    fn should_append_the_new_intervals_to_the_log() {
+        let dir = test_dir("append");
+        let storage = JsonStorage::new(&dir, "UTC", true);
+        let day = date(10, 16);
+        let first = interval(
+            "editor",
+            None,
+            "2026-10-16T08:00:00Z",
+            "2026-10-16T08:01:00Z",
+        );
+        let grown = interval(
+            "editor",
+            None,
+            "2026-10-16T08:00:00Z",
+            "2026-10-16T08:02:00Z",
+        );
+        let second = interval(
+            "browser",
+            None,
+            "2026-10-16T08:02:00Z",
+            "2026-10-16T08:03:00Z",
+        );
+        storage.save_day(day, &DashMap::new(), std::slice::from_ref(&first));
+        let path = dir.join("20261016-intervals");
+        let saved = std::fs::read_to_string(&path).unwrap();
+        storage.append_day(day, &DashMap::new(), &[grown.clone(), second.clone()]);
+        assert!(std::fs::read_to_string(&path).unwrap().starts_with(&saved));
+
+        let mut warnings = vec![];
+        assert_eq!(
+            vec![grown.clone(), second.clone()],
+            storage.load_day(day, &mut warnings).1
+        );
+        // Cut short by a crash
+        let mut log = File::options().append(true).open(&path).unwrap();
+        write!(log, "\n{{\"app_name\":\"edi").unwrap();
+        assert_eq!(
+            vec![grown, second],
+            storage.load_intervals(all_days(), &mut warnings)[&day]
+        );
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn should_start_empty_for_new_file() {
+        let dir = test_dir("new");
+        let mut warnings = vec![];
+        assert!(
+            load_data_from_file::<Vec<u32>>(&dir.join("20261016"), true, &mut warnings).is_empty()
+        );
+        assert!(warnings.is_empty());
+    }
+}
@@ -0,0 +1,428 @@
+use std::{collections::BTreeMap, ops::RangeInclusive, path::Path, sync::Mutex, time::Duration};
+
+use chrono::NaiveDate;
+use dashmap::DashMap;
+use rusqlite::{params, Connection, OptionalExtension};
+
+use crate::activity::Interval;
+
+use super::{all_days, JsonStorage, Storage};
+
+const DATABASE_FILE: &str = "time_back.sqlite";
+const SCHEMA_VERSION: u32 = 1;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS metadata (
+    key TEXT PRIMARY KEY,
+    value TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS day_totals (
+    day TEXT NOT NULL,
+    app_name TEXT NOT NULL,
+    nanos INTEGER NOT NULL,
+    PRIMARY KEY (day, app_name)
+);
+CREATE TABLE IF NOT EXISTS intervals (
+    day TEXT NOT NULL,
+    app_name TEXT NOT NULL,
+    title TEXT NOT NULL,
+    start TEXT NOT NULL,
+    end TEXT NOT NULL,
+    active INTEGER NOT NULL,
+    project TEXT,
+    category TEXT
+);
+CREATE INDEX IF NOT EXISTS intervals_day ON intervals (day);
+CREATE TABLE IF NOT EXISTS input_stats (
+    input TEXT PRIMARY KEY,
+    count INTEGER NOT NULL
+);
+";
+
+// Everything in a single database in the output directory
+pub struct SqliteStorage {
+    connection: Mutex<Connection>,
+}
+
+impl SqliteStorage {
+    pub fn open(directory: &Path, timezone: &str) -> Result<Self, rusqlite::Error> {
+        let path = directory.join(DATABASE_FILE);
+        let created = !path.exists();
+        let connection = Connection::open(&path)?;
+        // The tracking thread and the UI can both be saving
+        connection.busy_timeout(Duration::from_secs(5))?;
+        let version: u32 = connection.pragma_query_value(None, "user_version", |r| r.get(0))?;
+        if version > SCHEMA_VERSION {
+            return Err(rusqlite::Error::InvalidParameterName(format!(
+                "{:?} has schema version {}, this version only supports {}",
+                path, version, SCHEMA_VERSION
+            )));
+        }
+        // Opening is then read only, as long as the metadata is the same
+        if version < SCHEMA_VERSION {
+            connection.execute_batch(SCHEMA)?;
+            connection.pragma_update(None, "user_version", SCHEMA_VERSION)?;
+        }
+        for (key, value) in [
+            (
+                "host",
+                gethostname::gethostname().to_string_lossy().into_owned(),
+            ),
+            ("timezone", timezone.to_string()),
+            ("app_version", env!("CARGO_PKG_VERSION").to_string()),
+        ] {
+            let saved: Option<String> = connection
+                .query_row(
+                    "SELECT value FROM metadata WHERE key = ?1",
+                    params![key],
+                    |r| r.get(0),
+                )
+                .optional()?;
+            if saved.as_ref() != Some(&value) {
+                connection.execute(
+                    "INSERT OR REPLACE INTO metadata (key, value) VALUES (?1, ?2)",
+                    params![key, value],
+                )?;
+            }
+        }
+        let storage = Self {
+            connection: Mutex::new(connection),
+        };
+        if created {
+            // Switching backend shouldn't hide the history collected so far
+            storage.import(&JsonStorage::new(directory, timezone, false))?;
+        }
+        Ok(storage)
+    }
+
+    fn import(&self, source: &dyn Storage) -> Result<(), rusqlite::Error> {
+        let mut warnings = vec![];
+        let mut intervals = source.load_intervals(all_days(), &mut warnings);
+        for (day, totals) in source.load_totals(all_days(), &mut warnings) {
+            self.write_day(
+                day,
+                totals.into_iter(),
+                &intervals.remove(&day).unwrap_or_default(),
+                true,
+            )?;
+        }
+        self.write_input_stats(&source.load_input_stats(&mut warnings))?;
+        for warning in warnings {
+            eprintln!("Importing into the database: {}", warning);
+        }
+        Ok(())
+    }
+
+    // Only the given intervals are written when not replacing the whole day, the saved
+    // ones starting with the first of them or after are replaced
+    fn write_day(
+        &self,
+        day: NaiveDate,
+        totals: impl Iterator<Item = (String, Duration)>,
+        intervals: &[Interval],
+        whole_day: bool,
+    ) -> Result<(), rusqlite::Error> {
+        let mut connection = self.connection.lock().unwrap();
+        let transaction = connection.transaction()?;
+        transaction.execute("DELETE FROM day_totals WHERE day = ?1", params![day])?;
+        if whole_day {
+            transaction.execute("DELETE FROM intervals WHERE day = ?1", params![day])?;
+        } else if let Some(first) = intervals.first() {
+            transaction.execute(
+                "DELETE FROM intervals WHERE day = ?1 AND start >= ?2",
+                params![day, first.start],
+            )?;
+        }
+        {
+            let mut insert_total = transaction
+                .prepare("INSERT INTO day_totals (day, app_name, nanos) VALUES (?1, ?2, ?3)")?;
+            for (app_name, duration) in totals {
+                insert_total.execute(params![day, app_name, duration.as_nanos() as i64])?;
+            }
+            let mut insert_interval = transaction.prepare(
+                "INSERT INTO intervals (day, app_name, title, start, end, active, project, category)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            )?;
+            for i in intervals {
+                insert_interval.execute(params![
+                    day, i.app_name, i.title, i.start, i.end, i.active, i.project, i.category
+                ])?;
+            }
+        }
+        transaction.commit()
+    }
+
+    fn write_input_stats(&self, input_stats: &DashMap<String, u32>) -> Result<(), rusqlite::Error> {
+        let mut connection = self.connection.lock().unwrap();
+        let transaction = connection.transaction()?;
+        transaction.execute("DELETE FROM input_stats", [])?;
+        {
+            let mut insert =
+                transaction.prepare("INSERT INTO input_stats (input, count) VALUES (?1, ?2)")?;
+            for v in input_stats.iter() {
+                insert.execute(params![v.key(), v.value()])?;
+            }
+        }
+        transaction.commit()
+    }
+
+    fn read_totals(
+        &self,
+        days: &RangeInclusive<NaiveDate>,
+    ) -> Result<BTreeMap<NaiveDate, BTreeMap<String, Duration>>, rusqlite::Error> {
+        let connection = self.connection.lock().unwrap();
+        let mut statement = connection
+            .prepare("SELECT day, app_name, nanos FROM day_totals WHERE day BETWEEN ?1 AND ?2")?;
+        let (first, last) = text_bounds(days);
+        let rows = statement.query_map(params![first, last], |r| {
+            Ok((
+                r.get::<_, NaiveDate>(0)?,
+                r.get::<_, String>(1)?,
+                Duration::from_nanos(r.get::<_, i64>(2)?.max(0) as u64),
+            ))
+        })?;
+        let mut result: BTreeMap<NaiveDate, BTreeMap<String, Duration>> = BTreeMap::new();
+        for row in rows {
+            let (day, app_name, duration) = row?;
+            result.entry(day).or_default().insert(app_name, duration);
+        }
+        Ok(result)
+    }
+
+    fn read_intervals(
+        &self,
+        days: &RangeInclusive<NaiveDate>,
+    ) -> Result<BTreeMap<NaiveDate, Vec<Interval>>, rusqlite::Error> {
+        let connection = self.connection.lock().unwrap();
+        let mut statement = connection.prepare(
+            "SELECT day, app_name, title, start, end, active, project, category
+             FROM intervals WHERE day BETWEEN ?1 AND ?2 ORDER BY day, start",
+        )?;
+        let (first, last) = text_bounds(days);
+        let rows = statement.query_map(params![first, last], |r| {
+            Ok((
+                r.get::<_, NaiveDate>(0)?,
+                Interval {
+                    app_name: r.get(1)?,
+                    title: r.get(2)?,
+                    start: r.get(3)?,
+                    end: r.get(4)?,
+                    active: r.get(5)?,
+                    project: r.get(6)?,
+                    category: r.get(7)?,
+                },
+            ))
+        })?;
+        let mut result: BTreeMap<NaiveDate, Vec<Interval>> = BTreeMap::new();
+        for row in rows {
+            let (day, interval) = row?;
+            result.entry(day).or_default().push(interval);
+        }
+        Ok(result)
+    }
+
+    fn save(
+        &self,
+        day: NaiveDate,
+        totals: &DashMap<String, Duration>,
+        intervals: &[Interval],
+        whole_day: bool,
+    ) {
+        let totals = totals
+            .iter()
+            .map(|v| (v.key().clone(), *v.value()))
+            .collect::<Vec<_>>();
+        if let Err(e) = self.write_day(day, totals.into_iter(), intervals, whole_day) {
+            eprintln!("Error saving {} to the database: {}", day, e);
+        }
+    }
+
+    fn read_input_stats(&self) -> Result<DashMap<String, u32>, rusqlite::Error> {
+        let connection = self.connection.lock().unwrap();
+        let mut statement = connection.prepare("SELECT input, count FROM input_stats")?;
+        let rows = statement.query_map([], |r| Ok((r.get(0)?, r.get(1)?)))?;
+        rows.collect()
+    }
+}
+
+// Days are stored as text, outside of four digit years the order of the text doesn't
+// follow the order of the dates
+fn text_bounds(days: &RangeInclusive<NaiveDate>) -> (NaiveDate, NaiveDate) {
+    let first = NaiveDate::from_ymd_opt(0, 1, 1).unwrap();
+    let last = NaiveDate::from_ymd_opt(9999, 12, 31).unwrap();
+    (
+        (*days.start()).clamp(first, last),
+        (*days.end()).clamp(first, last),
+    )
+}
+
+impl Storage for SqliteStorage {
+    fn load_day(
+        &self,
+        day: NaiveDate,
+        warnings: &mut Vec<String>,
+    ) -> (DashMap<String, Duration>, Vec<Interval>) {
+        (
+            self.load_totals(day..=day, warnings)
+                .remove(&day)
+                .unwrap_or_default()
+                .into_iter()
+                .collect(),
+            self.load_intervals(day..=day, warnings)
+                .remove(&day)
+                .unwrap_or_default(),
+        )
+    }
+
+    fn save_day(&self, day: NaiveDate, totals: &DashMap<String, Duration>, intervals: &[Interval]) {
+        self.save(day, totals, intervals, true);
+    }
+
+    fn append_day(
+        &self,
+        day: NaiveDate,
+        totals: &DashMap<String, Duration>,
+        intervals: &[Interval],
+    ) {
+        self.save(day, totals, intervals, false);
+    }
+
+    fn load_totals(
+        &self,
+        days: RangeInclusive<NaiveDate>,
+        warnings: &mut Vec<String>,
+    ) -> BTreeMap<NaiveDate, BTreeMap<String, Duration>> {
+        self.read_totals(&days).unwrap_or_else(|e| {
+            warnings.push(format!(
+                "Unable to read the totals from the database: {}",
+                e
+            ));
+            BTreeMap::new()
+        })
+    }
+
+    fn load_intervals(
+        &self,
+        days: RangeInclusive<NaiveDate>,
+        warnings: &mut Vec<String>,
+    ) -> BTreeMap<NaiveDate, Vec<Interval>> {
+        self.read_intervals(&days).unwrap_or_else(|e| {
+            warnings.push(format!(
+                "Unable to read the intervals from the database: {}",
+                e
+            ));
+            BTreeMap::new()
+        })
+    }
+
+    fn load_input_stats(&self, warnings: &mut Vec<String>) -> DashMap<String, u32> {
+        self.read_input_stats().unwrap_or_else(|e| {
+            warnings.push(format!(
+                "Unable to read the input stats from the database: {}",
+                e
+            ));
+            DashMap::new()
+        })
+    }
+
+    fn save_input_stats(&self, input_stats: &DashMap<String, u32>) {
+        if let Err(e) = self.write_input_stats(input_stats) {
+            eprintln!("Error saving the input stats to the database: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::test_dir;
+
+    #[test]
+    fn should_save_and_load_days() {
+        let dir = test_dir("sqlite-days");
+        let storage = SqliteStorage::open(&dir, "UTC").unwrap();
+        let day = NaiveDate::from_ymd_opt(2026, 10, 16).unwrap();
+        let start = "2026-10-16T08:00:00Z".parse().unwrap();
+        let interval = Interval {
+            app_name: "editor".to_string(),
+            title: "main.rs".to_string(),
+            start,
+            end: start + chrono::Duration::minutes(5),
+            active: true,
+            project: Some("time_back".to_string()),
+            category: None,
+        };
+        let totals = DashMap::new();
+        totals.insert("editor".to_string(), Duration::from_secs(300));
+        storage.save_day(day, &totals, std::slice::from_ref(&interval));
+        // Saving again replaces the day
+        storage.save_day(day, &totals, std::slice::from_ref(&interval));
+
+        let mut warnings = vec![];
+        let (loaded_totals, loaded_intervals) = storage.load_day(day, &mut warnings);
+        assert_eq!(
+            Duration::from_secs(300),
+            *loaded_totals.get("editor").unwrap()
+        );
+        assert_eq!(vec![interval.clone()], loaded_intervals);
+        assert!(storage
+            .load_totals(day.succ_opt().unwrap()..=NaiveDate::MAX, &mut warnings)
+            .is_empty());
+
+        let grown = Interval {
+            end: start + chrono::Duration::minutes(10),
+            ..interval
+        };
+        let next = Interval {
+            start: grown.end,
+            end: grown.end + chrono::Duration::minutes(1),
+            ..grown.clone()
+        };
+        storage.append_day(day, &totals, &[grown.clone(), next.clone()]);
+        assert_eq!(vec![grown, next], storage.load_day(day, &mut warnings).1);
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn should_import_json_history_on_creation() {
+        let dir = test_dir("sqlite-import");
+        let json = JsonStorage::new(&dir, "UTC", true);
+        let day = NaiveDate::from_ymd_opt(2026, 10, 15).unwrap();
+        let totals = DashMap::new();
+        totals.insert("editor".to_string(), Duration::from_secs(60));
+        json.save_day(day, &totals, &[]);
+        let input_stats = DashMap::new();
+        input_stats.insert("A".to_string(), 3);
+        json.save_input_stats(&input_stats);
+
+        let storage = SqliteStorage::open(&dir, "UTC").unwrap();
+        let mut warnings = vec![];
+        assert_eq!(
+            Duration::from_secs(60),
+            storage.load_totals(all_days(), &mut warnings)[&day]["editor"]
+        );
+        assert_eq!(
+            3,
+            *storage.load_input_stats(&mut warnings).get("A").unwrap()
+        );
+    }
+
+    #[test]
+    fn should_only_write_the_changed_metadata_when_reopened() {
+        let dir = test_dir("sqlite-reopen");
+        SqliteStorage::open(&dir, "UTC").unwrap();
+        let reopened = SqliteStorage::open(&dir, "UTC").unwrap();
+        assert_eq!(0, reopened.connection.lock().unwrap().total_changes());
+        let moved = SqliteStorage::open(&dir, "Europe/Rome").unwrap();
+        let connection = moved.connection.lock().unwrap();
+        assert_eq!(1, connection.total_changes());
+        let timezone: String = connection
+            .query_row(
+                "SELECT value FROM metadata WHERE key = 'timezone'",
+                [],
+                |r| r.get(0),
+            )
+            .unwrap();
+        assert_eq!("Europe/Rome", timezone);
+    }
+}
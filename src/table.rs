@@ -0,0 +1,136 @@
+use std::time::Duration;
+
+use dashmap::DashMap;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SortColumn {
+    Name,
+    Duration,
+    Share,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TableSort {
+    pub column: SortColumn,
+    pub descending: bool,
+}
+
+impl Default for TableSort {
+    fn default() -> Self {
+        Self {
+            column: SortColumn::Duration,
+            descending: true,
+        }
+    }
+}
+
+impl TableSort {
+    /// Clicking the sorted column flips the order, any other column starts with the
+    /// order that makes sense for it
+    pub fn toggle(&mut self, column: SortColumn) {
+        if self.column == column {
+            self.descending = !self.descending;
+        } else {
+            self.column = column;
+            self.descending = column != SortColumn::Name;
+        }
+    }
+
+    pub fn arrow(&self, column: SortColumn) -> &'static str {
+        match (self.column == column, self.descending) {
+            (false, _) => "",
+            (true, true) => " ▼",
+            (true, false) => " ▲",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Row {
+    pub name: String,
+    pub duration: Duration,
+    /// Percentage of the time of all the applications, filtered out ones included
+    pub share: f64,
+}
+
+/// Applications whose name contains the filter, ignoring case, in the order of the
+/// sort. Equal values are ordered by name so the rows don't move between frames.
+pub fn rows(window_time: &DashMap<String, Duration>, sort: TableSort, filter: &str) -> Vec<Row> {
+    let overall = window_time.iter().map(|v| *v.value()).sum::<Duration>();
+    let filter = filter.trim().to_lowercase();
+    let mut rows = window_time
+        .iter()
+        .filter(|v| v.key().to_lowercase().contains(&filter))
+        .map(|v| Row {
+            name: v.key().clone(),
+            duration: *v.value(),
+            share: if overall.is_zero() {
+                0.
+            } else {
+                v.value().as_secs_f64() * 100. / overall.as_secs_f64()
+            },
+        })
+        .collect::<Vec<_>>();
+    rows.sort_by(|a, b| {
+        let by_name = || {
+            a.name
+                .to_lowercase()
+                .cmp(&b.name.to_lowercase())
+                .then_with(|| a.name.cmp(&b.name))
+        };
+        let ordering = match sort.column {
+            SortColumn::Name => by_name(),
+            SortColumn::Duration => a.duration.cmp(&b.duration),
+            SortColumn::Share => a.share.total_cmp(&b.share),
+        };
+        let ordering = if sort.descending {
+            ordering.reverse()
+        } else {
+            ordering
+        };
+        ordering.then_with(by_name)
+    });
+    rows
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn window_time() -> DashMap<String, Duration> {
+        let window_time = DashMap::new();
+        window_time.insert("editor".to_string(), Duration::from_secs(60));
+        window_time.insert("Chat".to_string(), Duration::from_secs(20));
+        window_time.insert("browser".to_string(), Duration::from_secs(20));
+        window_time
+    }
+
+    fn names(rows: &[Row]) -> Vec<&str> {
+        rows.iter().map(|r| r.name.as_str()).collect()
+    }
+
+    #[test]
+    fn should_sort_by_duration_descending_by_default() {
+        let rows = rows(&window_time(), TableSort::default(), "");
+        assert_eq!(vec!["editor", "browser", "Chat"], names(&rows));
+        assert_eq!(60., rows[0].share);
+    }
+
+    #[test]
+    fn should_toggle_sort_and_filter() {
+        let mut sort = TableSort::default();
+        sort.toggle(SortColumn::Name);
+        assert_eq!(
+            vec!["browser", "Chat", "editor"],
+            names(&rows(&window_time(), sort, ""))
+        );
+        sort.toggle(SortColumn::Name);
+        assert_eq!(
+            vec!["editor", "Chat", "browser"],
+            names(&rows(&window_time(), sort, ""))
+        );
+        let rows = rows(&window_time(), TableSort::default(), " CH");
+        assert_eq!(vec!["Chat"], names(&rows));
+        assert_eq!(20., rows[0].share);
+    }
+}
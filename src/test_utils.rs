@@ -0,0 +1,29 @@
+use std::path::PathBuf;
+
+use chrono::NaiveDate;
+
+use crate::activity::Interval;
+
+pub fn date(month: u32, day: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(2026, month, day).unwrap()
+}
+
+pub fn interval(app_name: &str, category: Option<&str>, start: &str, end: &str) -> Interval {
+    Interval {
+        app_name: app_name.to_string(),
+        title: String::new(),
+        start: start.parse().unwrap(),
+        end: end.parse().unwrap(),
+        active: true,
+        project: None,
+        category: category.map(|c| c.to_string()),
+    }
+}
+
+// Empty directory of its own for each test, the tests run in parallel
+pub fn test_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("time_back-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
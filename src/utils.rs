@@ -1,59 +1,70 @@
-use std::{collections::BTreeMap, time::Duration};
+use std::time::{Duration, Instant};
 
-pub fn generate_file_name() -> String {
-    chrono::Local::now()
-        .date_naive()
-        .to_string()
-        .replace('-', "")
+use chrono::{DateTime, Local, NaiveDate, NaiveTime, Utc};
+use chrono_tz::Tz;
+
+// As often as the tracked data is saved
+const CACHE_REFRESH: Duration = Duration::from_secs(5);
+
+pub fn day_file_name(day: NaiveDate) -> String {
+    day.format("%Y%m%d").to_string()
 }
-pub fn calculate_sum(data: &BTreeMap<String, Vec<Duration>>) -> Vec<(&str, f64)> {
-    let mut result_sum = data
-        .iter()
-        .map(|(k, v)| (k.as_str(), v.iter().sum::<Duration>().as_secs_f64()))
-        .collect::<Vec<_>>();
-    result_sum.sort_unstable_by(|a, b| b.1.total_cmp(&a.1));
-    result_sum
+
+// Shaped like a day file, the date itself could still be invalid
+pub fn is_day_file_name(name: &str) -> bool {
+    name.len() == 8 && name.bytes().all(|b| b.is_ascii_digit())
 }
 
-pub fn calculate_avg(data: &BTreeMap<String, Vec<Duration>>) -> Vec<(&str, f64)> {
-    let mut result_avg = data
-        .iter()
-        .map(|(k, v)| {
-            (
-                k.as_str(),
-                v.iter().sum::<Duration>().as_secs_f64() / v.len() as f64,
-            )
-        })
-        .collect::<Vec<_>>();
-    result_avg.sort_unstable_by(|a, b| b.1.total_cmp(&a.1));
-    result_avg
+pub fn parse_day_file_name(name: &str) -> Option<NaiveDate> {
+    if !is_day_file_name(name) {
+        return None;
+    }
+    NaiveDate::parse_from_str(name, "%Y%m%d").ok()
+}
+
+/// Work day the given instant belongs to: days start at `day_start` on the wall clock of
+/// the timezone, or of the system one when none is configured so it follows the laptop
+pub fn logical_day(now: DateTime<Utc>, day_start: NaiveTime, timezone: Option<Tz>) -> NaiveDate {
+    let local = match timezone {
+        Some(timezone) => now.with_timezone(&timezone).naive_local(),
+        None => now.with_timezone(&Local).naive_local(),
+    };
+    // Working on the wall clock keeps the boundary at the same local time across DST changes
+    (local - day_start.signed_duration_since(NaiveTime::MIN)).date()
 }
 
-pub fn calculate_median(data: &BTreeMap<String, Vec<Duration>>) -> Vec<(&str, f64)> {
-    let mut result_median = data
-        .iter()
-        .map(|(k, v)| {
-            let mut v = v.clone();
-            v.sort();
-            (
-                k.as_str(),
-                if v.len() % 2 == 0 {
-                    let middle = v.len() / 2;
-                    if middle >= 1 {
-                        (v[middle - 1] + v[middle]).as_secs_f64() / 2.
-                    } else {
-                        0.
-                    }
-                } else if !v.is_empty() {
-                    v[v.len() / 2].as_secs_f64()
-                } else {
-                    0.
-                },
-            )
+/// Average of each value with the ones before it, the first values use the ones available
+pub fn moving_average(values: &[f64], window: usize) -> Vec<f64> {
+    let window = window.max(1);
+    (0..values.len())
+        .map(|i| {
+            let slice = &values[(i + 1).saturating_sub(window)..=i];
+            slice.iter().sum::<f64>() / slice.len() as f64
         })
-        .collect::<Vec<_>>();
-    result_median.sort_unstable_by(|a, b| b.1.total_cmp(&a.1));
-    result_median
+        .collect()
+}
+
+/// Value too slow to build every frame, built again when the key it comes from changes
+/// and every few seconds for the time tracked, or the history reloaded, meanwhile
+pub struct Cached<K, V>(Option<(K, Instant, V)>);
+
+impl<K, V> Default for Cached<K, V> {
+    fn default() -> Self {
+        Self(None)
+    }
+}
+
+impl<K: PartialEq, V> Cached<K, V> {
+    pub fn get(&mut self, key: K, build: impl FnOnce() -> V) -> &V {
+        let fresh = matches!(
+            &self.0,
+            Some((built_for, built, _)) if *built_for == key && built.elapsed() < CACHE_REFRESH
+        );
+        if !fresh {
+            self.0 = Some((key, Instant::now(), build()));
+        }
+        &self.0.as_ref().unwrap().2
+    }
 }
 
 #[cfg(test)]
@@ -61,72 +72,78 @@ mod tests {
     use super::*;
 
     #[test]
-    fn should_calculate_median_with_no_data() {
-        let data: BTreeMap<String, Vec<Duration>> = BTreeMap::new();
-        let vec: Vec<(&str, f64)> = vec![];
-        assert_eq!(vec, calculate_median(&data));
+    fn should_use_calendar_day_by_default() {
+        let now = "2026-10-15T23:30:00Z".parse().unwrap();
+        assert_eq!(
+            NaiveDate::from_ymd_opt(2026, 10, 15).unwrap(),
+            logical_day(now, NaiveTime::MIN, Some(Tz::UTC))
+        );
     }
 
     #[test]
-    fn should_calculate_median_with_single_data_odd() {
-        let mut data: BTreeMap<String, Vec<Duration>> = BTreeMap::new();
-        data.insert(
-            "time_back".to_string(),
-            vec![
-                Duration::from_secs(1),
-                Duration::from_secs(15),
-                Duration::from_secs(40),
-                Duration::from_secs(2),
-                Duration::from_secs(4),
-            ],
+    fn should_count_night_hours_in_previous_day() {
+        let day_start = NaiveTime::from_hms_opt(4, 0, 0).unwrap();
+        let timezone = Some(Tz::Europe__Rome);
+        // 01:30 in Rome
+        let now = "2026-10-15T23:30:00Z".parse().unwrap();
+        assert_eq!(
+            NaiveDate::from_ymd_opt(2026, 10, 15).unwrap(),
+            logical_day(now, day_start, timezone)
+        );
+        // 04:30 in Rome
+        let now = "2026-10-16T02:30:00Z".parse().unwrap();
+        assert_eq!(
+            NaiveDate::from_ymd_opt(2026, 10, 16).unwrap(),
+            logical_day(now, day_start, timezone)
         );
-        let vec = vec![("time_back", 4.0)];
-        assert_eq!(vec, calculate_median(&data));
     }
 
     #[test]
-    fn should_calculate_median_with_single_data_even() {
-        let mut data: BTreeMap<String, Vec<Duration>> = BTreeMap::new();
-        data.insert(
-            "time_back".to_string(),
-            vec![
-                Duration::from_secs(5),
-                Duration::from_secs(1),
-                Duration::from_secs(15),
-                Duration::from_secs(40),
-                Duration::from_secs(2),
-                Duration::from_secs(4),
-            ],
+    fn should_keep_day_start_on_wall_clock_across_dst() {
+        let day_start = NaiveTime::from_hms_opt(4, 0, 0).unwrap();
+        let timezone = Some(Tz::Europe__Rome);
+        // Clocks go back from 03:00 CEST to 02:00 CET at 01:00 UTC
+        let now = "2026-10-25T02:30:00Z".parse().unwrap();
+        assert_eq!(
+            NaiveDate::from_ymd_opt(2026, 10, 24).unwrap(),
+            logical_day(now, day_start, timezone)
+        );
+        let now = "2026-10-25T03:00:00Z".parse().unwrap();
+        assert_eq!(
+            NaiveDate::from_ymd_opt(2026, 10, 25).unwrap(),
+            logical_day(now, day_start, timezone)
+        );
+        // Clocks go forward from 02:00 CET to 03:00 CEST at 01:00 UTC
+        let now = "2026-03-29T01:30:00Z".parse().unwrap();
+        assert_eq!(
+            NaiveDate::from_ymd_opt(2026, 3, 28).unwrap(),
+            logical_day(now, day_start, timezone)
+        );
+        let now = "2026-03-29T02:00:00Z".parse().unwrap();
+        assert_eq!(
+            NaiveDate::from_ymd_opt(2026, 3, 29).unwrap(),
+            logical_day(now, day_start, timezone)
         );
-        let vec = vec![("time_back", 4.5)];
-        assert_eq!(vec, calculate_median(&data));
     }
 
     #[test]
-    fn should_calculate_median_with_multiple_odd_data() {
-        let mut data: BTreeMap<String, Vec<Duration>> = BTreeMap::new();
-        data.insert(
-            "time_back".to_string(),
-            vec![
-                Duration::from_secs(5),
-                Duration::from_secs(1),
-                Duration::from_secs(15),
-                Duration::from_secs(40),
-                Duration::from_secs(2),
-                Duration::from_secs(4),
-            ],
-        );
-        data.insert(
-            "something-else".to_string(),
-            vec![
-                Duration::from_secs(4),
-                Duration::from_secs(10),
-                Duration::from_secs(1),
-                Duration::from_secs(20),
-                Duration::from_secs(41),
-            ],
-        );
-        let vec = vec![("something-else", 10.), ("time_back", 4.5)];
-        assert_eq!(vec, calculate_median(&data));
+    fn should_calculate_moving_average() {
+        assert_eq!(vec![2., 3., 4., 6.], moving_average(&[2., 4., 6., 8.], 3));
+        assert_eq!(vec![2., 4.], moving_average(&[2., 4.], 1));
+        assert!(moving_average(&[], 7).is_empty());
+    }
+
+    #[test]
+    fn should_build_again_only_for_a_new_key() {
+        let mut cached = Cached::default();
+        let mut builds = 0;
+        for key in [1, 1, 2, 2] {
+            cached.get(key, || {
+                builds += 1;
+                key * 10
+            });
+        }
+        assert_eq!(2, builds);
+        assert_eq!(20, *cached.get(2, || 0));
     }
 }